@@ -1,12 +1,9 @@
 #![no_std]
 #![no_main]
-#![feature(type_alias_impl_trait)]
-#![allow(incomplete_features)]
 #![allow(async_fn_in_trait)]
+#![cfg_attr(feature = "wifi", feature(type_alias_impl_trait))]
 
-pub mod apds9960;
-pub mod color;
-pub mod led_matrix;
-pub mod perlin;
-pub mod world;
+pub mod settings;
+#[cfg(feature = "wifi")]
+pub mod wifi;
 pub mod ws2812;
@@ -2,20 +2,34 @@
 #![no_main]
 #![feature(type_alias_impl_trait)]
 
+use core::cell::Cell;
+use core::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use defmt::*;
 use defmt_rtt as _;
 use embassy_executor::Spawner;
 use embassy_rp::bind_interrupts;
+use embassy_rp::clocks::RoscRng;
+use embassy_rp::gpio::{Input, Pull};
 use embassy_rp::i2c::{self, Async, Config, InterruptHandler as I2CInterruptHandler};
-use embassy_rp::peripherals::{I2C0, PIO0};
+use embassy_rp::peripherals::{I2C0, PIN_19, PIO0};
 use embassy_rp::pio::{InterruptHandler as PioInterruptHandler, Pio};
 use embassy_sync::blocking_mutex::raw::ThreadModeRawMutex;
+use embassy_sync::blocking_mutex::Mutex;
 use embassy_sync::channel::Channel;
-use embassy_time::{Duration, Ticker};
-use pleiades::apds9960::{Apds9960, Command};
-use pleiades::led_matrix::LedMatrix;
-use pleiades::world::{OnDirection, Switch, World};
+use embassy_sync::signal::Signal;
+use embassy_time::{Duration, Instant, Ticker, Timer};
+use pleiades::settings::{self, Settings};
 use pleiades::ws2812::Ws2812;
+use pleiades_core::apds9960::{self, Apds9960};
+use pleiades_core::api::Status;
+use pleiades_core::calibration::{self, Calibration};
+use pleiades_core::gesture::{Command, Direction, GestureConfig};
+use pleiades_core::led_matrix::{self, Correction, LedMatrix, PowerBudget};
+use pleiades_core::proximity::ProximityConfig;
+use pleiades_core::random::SeededRandom;
+use pleiades_core::settings::State;
+use pleiades_core::world::{OnDirection, Switch, World};
+use rand::RngCore;
 
 #[cfg(feature = "panic-probe")]
 use panic_probe as _;
@@ -26,13 +40,83 @@ const NUM_LEDS_LINE: usize = 16;
 const NUM_LEDS_COLUMN: usize = 16;
 const NUM_LEDS: usize = NUM_LEDS_LINE * NUM_LEDS_COLUMN;
 const STATE_MACHINE: usize = 0;
+// WS2812B are 5050 LEDs, whites at daylight
+const CORRECTION: Correction = Correction {
+    gamma: 2.2,
+    color: led_matrix::TYPICAL_SMD5050,
+    temperature: led_matrix::DIRECT_SUNLIGHT,
+};
 
 bind_interrupts!(struct Irqs {
     I2C0_IRQ => I2CInterruptHandler<I2C0>;
     PIO0_IRQ_0 => PioInterruptHandler<PIO0>;
 });
 
+#[cfg(feature = "console")]
+bind_interrupts!(struct ConsoleIrqs {
+    UART0_IRQ => embassy_rp::uart::BufferedInterruptHandler<embassy_rp::peripherals::UART0>;
+});
+
 static CHANNEL: Channel<ThreadModeRawMutex, Command, 1> = Channel::new();
+// New gesture tuning from the console, picked up by the sensor task
+// before its next reading
+static GESTURE_CONFIG: Channel<ThreadModeRawMutex, GestureConfig, 1> = Channel::new();
+// Gesture tuning last sent, `None` for the defaults
+#[cfg(feature = "console")]
+static GESTURE_TUNING: Mutex<ThreadModeRawMutex, Cell<Option<GestureConfig>>> =
+    Mutex::new(Cell::new(None));
+// New gain, LED current, pulses or wait time for the proximity readings,
+// from the console
+static PROXIMITY_CONFIG: Channel<ThreadModeRawMutex, ProximityConfig, 1> = Channel::new();
+// Proximity config last sent, `None` for the defaults
+#[cfg(feature = "console")]
+static PROXIMITY_TUNING: Mutex<ThreadModeRawMutex, Cell<Option<ProximityConfig>>> =
+    Mutex::new(Cell::new(None));
+static SENSOR: Channel<ThreadModeRawMutex, Sensor, 1> = Channel::new();
+// Calibrate the proximity offset, on a gesture or a remote command
+static CALIBRATE: Channel<ThreadModeRawMutex, (), 1> = Channel::new();
+// What the remotes see, published by the main loop
+static STATUS: Mutex<ThreadModeRawMutex, Cell<Status>> = Mutex::new(Cell::new(Status {
+    world: 0,
+    on: true,
+    brightness: u8::MAX,
+}));
+static BRIGHTNESS: AtomicU8 = AtomicU8::new(u8::MAX);
+// Latest proximity reading and whether the sensor answers, for the console
+static PROXIMITY: AtomicU8 = AtomicU8::new(0);
+static SENSOR_PRESENT: AtomicBool = AtomicBool::new(true);
+// What to start with after a power cycle, saved by the settings task
+static SAVE: Signal<ThreadModeRawMutex, State> = Signal::new();
+static SAVE_OFFSET: Signal<ThreadModeRawMutex, i8> = Signal::new();
+// Commands from the gestures, published as events over MQTT
+#[cfg(feature = "mqtt")]
+static GESTURES: Channel<ThreadModeRawMutex, Command, 4> = Channel::new();
+// Latest pixels streamed over the network, older frames are dropped
+#[cfg(feature = "stream")]
+static FRAMES: Signal<ThreadModeRawMutex, pleiades_core::stream::Frame<NUM_LEDS>> = Signal::new();
+
+// Without the gesture sensor the lantern shows the diagnostic pattern,
+// then cycles through the worlds while the bus is rescanned
+const DIAGNOSTIC_TIME: Duration = Duration::from_secs(5);
+const PLAYLIST_PERIOD: Duration = Duration::from_secs(60);
+const RESCAN_PERIOD: Duration = Duration::from_secs(5);
+// Failed reads in a row before the sensor is taken as gone
+const LOST_AFTER: usize = 10;
+
+// Time to take the hand away before the idle baseline is measured
+const CALIBRATION_DELAY: Duration = Duration::from_secs(2);
+const CALIBRATION_SAMPLES: usize = 16;
+const SAMPLE_PERIOD: Duration = Duration::from_millis(10);
+
+// Changes settle this long before they are saved, a brightness slider
+// sends many
+const SAVE_DELAY: Duration = Duration::from_secs(5);
+
+#[derive(Clone, Copy)]
+enum Sensor {
+    Missing,
+    Present,
+}
 
 #[embassy_executor::main]
 async fn main(spawner: Spawner) {
@@ -43,12 +127,59 @@ async fn main(spawner: Spawner) {
     let sda = p.PIN_20;
     let scl = p.PIN_21;
 
-    // Init I2C and Apds9960 gesture sensor 
+    // Init I2C and Apds9960 gesture sensor
     let i2c = i2c::I2c::new_async(p.I2C0, scl, sda, Irqs, Config::default());
-    let apds = Apds9960::new(i2c);
+    let apds = Apds9960::new(i2c, GestureConfig::default());
+    // Flash reads only fail out of bounds
+    let settings = unwrap!(settings::open(p.FLASH));
+    let state = settings.state();
+    let offset = settings.proximity_offset();
+    unwrap!(spawner.spawn(settings_task(settings)));
+
+    // INT is open drain, active low
+    let int = Input::new(p.PIN_19, Pull::Up);
 
     // Start sensor_task asynchronously
-    unwrap!(spawner.spawn(sensor_task(apds)));
+    unwrap!(spawner.spawn(sensor_task(apds, int, offset)));
+
+    #[cfg(feature = "wifi")]
+    {
+        // sACN sources may send the universes to their multicast groups
+        #[cfg(feature = "stream")]
+        let multicast = stream_layout()
+            .universes(NUM_LEDS)
+            .map(pleiades_core::stream::e131::multicast);
+        #[cfg(not(feature = "stream"))]
+        let multicast = [];
+        let stack = pleiades::wifi::start(
+            &spawner, p.PIN_23, p.PIN_25, p.PIN_24, p.PIN_29, p.PIO1, p.DMA_CH1, multicast,
+        )
+        .await;
+        unwrap!(spawner.spawn(api_task(stack)));
+        #[cfg(feature = "mqtt")]
+        unwrap!(spawner.spawn(mqtt_task(stack)));
+        #[cfg(feature = "stream")]
+        unwrap!(spawner.spawn(stream_task(stack)));
+    }
+
+    #[cfg(feature = "console")]
+    {
+        use embassy_rp::uart::{self, BufferedUart};
+        use static_cell::StaticCell;
+
+        static TX: StaticCell<[u8; 256]> = StaticCell::new();
+        static RX: StaticCell<[u8; 64]> = StaticCell::new();
+        let uart = BufferedUart::new(
+            p.UART0,
+            ConsoleIrqs,
+            p.PIN_0,
+            p.PIN_1,
+            TX.init([0; 256]),
+            RX.init([0; 64]),
+            uart::Config::default(),
+        );
+        unwrap!(spawner.spawn(console_task(uart)));
+    }
 
     // Init PIO to support WS2812 protocol
     let Pio {
@@ -61,50 +192,464 @@ async fn main(spawner: Spawner) {
 
     // Init 16x16 LED matrix controller
     let mut led_matrix: LedMatrix<Ws2812<PIO0, 0, NUM_LEDS>, NUM_LEDS_LINE, NUM_LEDS> =
-        LedMatrix::new(&mut ws2812);
+        LedMatrix::with_brightness(&mut ws2812, &BRIGHTNESS);
+    let limit = option_env!("POWER_LIMIT")
+        .map(|limit| unwrap!(limit.parse().ok(), "POWER_LIMIT is not a current in mA"));
+    led_matrix.set_correction(&CORRECTION);
+    led_matrix.set_power_budget(PowerBudget {
+        limit: limit.unwrap_or(PowerBudget::default().limit),
+        ..PowerBudget::default()
+    });
+
+    // Seed the worlds from the ring oscillator.
+    // Same seed reproduces the same animation on the simulator.
+    let mut rng = SeededRandom::new(RoscRng.next_u64());
+    info!("Seed: {}", rng.seed());
+
+    // Back where the lantern was before the power cycle
+    BRIGHTNESS.store(state.status.brightness, Ordering::Relaxed);
+    let mut switch = Switch::restore(state.status.world, state.status.on, state.parameters);
+    let mut saved = state;
 
     // Create a new world
-    let mut world: World<'_, _, NUM_LEDS_COLUMN, NUM_LEDS_LINE, NUM_LEDS, { 2 * NUM_LEDS }> =
-        World::fire_new(&mut led_matrix);
+    let mut world: World<'_, _, _, NUM_LEDS_COLUMN, NUM_LEDS_LINE, NUM_LEDS, { 2 * NUM_LEDS }> =
+        switch.current_world(&mut led_matrix, &mut rng);
     // > = World::matrix_from(ws2812);
     // > = World::northen_light_from(ws2812);
     // > = World::voronoi_from(ws2812);
 
-    let mut switch = Switch::new();
+    // Next playlist switch while the gesture sensor is missing, and the
+    // worlds it steps through. Apart from `switch`, which is saved.
+    let mut playlist: Option<(Instant, Switch)> = None;
+    #[cfg(feature = "stream")]
+    let mut streaming = pleiades_core::stream::Watchdog::new();
 
     loop {
+        // Streamed pixels take over the matrix, the world comes back once
+        // they stop
+        #[cfg(feature = "stream")]
+        {
+            if FRAMES.signaled() {
+                let frame = FRAMES.wait().await;
+                if streaming.feed(Instant::now()) {
+                    defmt::info!("Stream started");
+                }
+                // Also after a gesture or the playlist switched away
+                if !matches!(world, World::Stream(_)) {
+                    world = World::stream_new(&mut led_matrix, &mut rng);
+                }
+                if let World::Stream(stream) = &mut world {
+                    stream.show(&frame.pixels);
+                }
+            }
+            if streaming.expired(Instant::now()) {
+                defmt::info!("Stream stopped");
+                world = switch.current_world(&mut led_matrix, &mut rng);
+            }
+        }
+
+        if let Ok(sensor) = SENSOR.try_receive() {
+            match sensor {
+                Sensor::Missing => {
+                    world = World::diagnostic_new(&mut led_matrix, &mut rng);
+                    let tour = Switch::restore(switch.world(), true, switch.parameters());
+                    playlist = Some((Instant::now() + DIAGNOSTIC_TIME, tour));
+                }
+                Sensor::Present => {
+                    world = switch.current_world(&mut led_matrix, &mut rng);
+                    playlist = None;
+                }
+            }
+        }
+
+        if let Some((next, tour)) = playlist
+            .as_mut()
+            .filter(|(next, _)| Instant::now() >= *next)
+        {
+            world = match switch.is_on() {
+                true => tour.switch_world(&mut led_matrix, &mut rng),
+                // Switched off, the diagnostic is over
+                false => switch.current_world(&mut led_matrix, &mut rng),
+            };
+            *next = Instant::now() + PLAYLIST_PERIOD;
+        }
+
         // Handle the command from the gesture sensor
         if let Ok(command) = CHANNEL.try_receive() {
             // defmt::info!("Command!: {}", command);
             match command {
-                Command::Level(direction) => world.on_direction(direction),
-                Command::Swing => world = switch.switch_world(&mut led_matrix),
-                Command::SwitchPower => world = switch.switch_power(&mut led_matrix),
+                Command::Swipe(Direction::Right) => {
+                    world = switch.switch_world(&mut led_matrix, &mut rng)
+                }
+                Command::Swipe(Direction::Left) => {
+                    world = switch.previous_world(&mut led_matrix, &mut rng)
+                }
+                // A hand held close over the gesture engine
+                Command::Swipe(Direction::Near) => {
+                    world = switch.switch_power(&mut led_matrix, &mut rng)
+                }
+                Command::Swipe(Direction::Far) => {}
+                Command::Level(direction) | Command::Swipe(direction) => {
+                    world.on_direction(direction);
+                    switch.remember(&world);
+                }
+                Command::Swing => world = switch.switch_world(&mut led_matrix, &mut rng),
+                Command::SwitchPower => world = switch.switch_power(&mut led_matrix, &mut rng),
+                Command::Power(on) => {
+                    if switch.set_power(on) {
+                        world = switch.current_world(&mut led_matrix, &mut rng)
+                    }
+                }
+                Command::Calibrate => {
+                    if let Err(_err) = CALIBRATE.try_send(()) {
+                        defmt::error!("Calibration already pending");
+                    }
+                }
+                Command::World(index) => {
+                    world = switch.select_world(index, &mut led_matrix, &mut rng)
+                }
+                Command::Brightness(level) => BRIGHTNESS.store(level, Ordering::Relaxed),
             }
         }
 
+        let status = Status {
+            world: switch.world(),
+            on: switch.is_on(),
+            brightness: BRIGHTNESS.load(Ordering::Relaxed),
+        };
+        STATUS.lock(|cell| cell.set(status));
+        let state = State {
+            status,
+            parameters: switch.parameters(),
+        };
+        if state != saved {
+            SAVE.signal(state);
+            saved = state;
+        }
+
         // World::tick is generated by macros
         World::tick(&mut world).await;
     }
 }
 
+/// The lantern as the network services and the console drive it
+#[cfg(any(feature = "wifi", feature = "console"))]
+struct Remote;
+
+#[cfg(any(feature = "wifi", feature = "console"))]
+impl pleiades_core::api::Lantern for Remote {
+    fn status(&self) -> Status {
+        STATUS.lock(|status| status.get())
+    }
+
+    async fn send(&self, command: Command) {
+        CHANNEL.send(command).await;
+    }
+}
+
+#[cfg(feature = "console")]
+impl pleiades_core::console::Console for Remote {
+    fn proximity(&self) -> Option<u8> {
+        SENSOR_PRESENT
+            .load(Ordering::Relaxed)
+            .then(|| PROXIMITY.load(Ordering::Relaxed))
+    }
+
+    fn gesture_config(&self) -> GestureConfig {
+        GESTURE_TUNING.lock(|config| config.get().unwrap_or_default())
+    }
+
+    async fn set_gesture_config(&self, config: GestureConfig) {
+        GESTURE_TUNING.lock(|tuning| tuning.set(Some(config)));
+        GESTURE_CONFIG.send(config).await;
+    }
+
+    fn proximity_config(&self) -> ProximityConfig {
+        PROXIMITY_TUNING.lock(|config| config.get().unwrap_or_default())
+    }
+
+    async fn set_proximity_config(&self, config: ProximityConfig) {
+        PROXIMITY_TUNING.lock(|tuning| tuning.set(Some(config)));
+        PROXIMITY_CONFIG.send(config).await;
+    }
+}
+
+#[cfg(feature = "mqtt")]
+impl pleiades_core::mqtt::client::Gestures for Remote {
+    async fn gesture(&self) -> Command {
+        GESTURES.receive().await
+    }
+}
+
+#[cfg(feature = "console")]
 #[embassy_executor::task]
-async fn sensor_task(mut apds: Apds9960<'static, I2C0, Async>) -> ! {
-    apds.enable().await.unwrap();
-    apds.powerup().await.unwrap();
+async fn console_task(
+    mut uart: embassy_rp::uart::BufferedUart<'static, embassy_rp::peripherals::UART0>,
+) -> ! {
+    use embedded_io_async::{Read, Write};
+    use pleiades_core::console::{self, LineBuffer, PROMPT};
 
-    let mut ticker = Ticker::every(Duration::from_millis(10));
+    let mut line: LineBuffer<64> = LineBuffer::new();
+    let mut input = [0u8; 16];
 
     loop {
-        // if let Ok(d) = apds.read().await {
-        //     defmt::info!("Dist: {}", d);
-        // }
-        apds.gesture().await;
-        if let Some(command) = apds.command() {
-            if let Err(_err) = CHANNEL.try_send(command) {
-                defmt::error!("Command channel buffer is full");
+        let read = match uart.read(&mut input).await {
+            Ok(read) => read,
+            Err(err) => {
+                defmt::error!("Console read failed: {}", err);
+                continue;
+            }
+        };
+
+        for byte in &input[..read] {
+            // Echo what the line buffer keeps
+            let echo: &[u8] = match byte {
+                0x08 | 0x7F => b"\x08 \x08",
+                byte if byte.is_ascii_graphic() || *byte == b' ' => core::slice::from_ref(byte),
+                _ => b"",
+            };
+            let _ = uart.write_all(echo).await;
+
+            if let Some(text) = line.push(*byte) {
+                let mut out: heapless::String<512> = heapless::String::new();
+                let _ = console::dispatch(text, &Remote, &mut out).await;
+                let _ = uart.write_all(b"\r\n").await;
+                let _ = uart.write_all(out.as_bytes()).await;
+                let _ = uart.write_all(PROMPT.as_bytes()).await;
             }
         }
-        ticker.next().await;
     }
 }
+
+#[cfg(feature = "wifi")]
+#[embassy_executor::task]
+async fn api_task(stack: &'static embassy_net::Stack<pleiades::wifi::Device>) -> ! {
+    use pleiades_core::api::server;
+
+    stack.wait_config_up().await;
+    server::serve(stack, server::PORT, &Remote, NUM_LEDS as u16).await
+}
+
+/// The universes from `STREAM_UNIVERSE` on
+#[cfg(feature = "stream")]
+fn stream_layout() -> pleiades_core::stream::Layout {
+    use pleiades_core::stream::Layout;
+
+    let universe = option_env!("STREAM_UNIVERSE").map(|universe| {
+        unwrap!(
+            universe.parse().ok(),
+            "STREAM_UNIVERSE is not a universe number"
+        )
+    });
+    Layout {
+        universe: universe.unwrap_or(Layout::default().universe),
+        ..Layout::default()
+    }
+}
+
+#[cfg(feature = "stream")]
+#[embassy_executor::task]
+async fn stream_task(stack: &'static embassy_net::Stack<pleiades::wifi::Device>) -> ! {
+    use pleiades_core::stream::receiver;
+
+    stack.wait_config_up().await;
+    receiver::receive(stack, &stream_layout(), |frame| FRAMES.signal(*frame)).await
+}
+
+#[cfg(feature = "mqtt")]
+#[embassy_executor::task]
+async fn mqtt_task(stack: &'static embassy_net::Stack<pleiades::wifi::Device>) -> ! {
+    use pleiades_core::mqtt::{client, Config};
+
+    let broker = unwrap!(
+        env!("MQTT_BROKER").parse().ok(),
+        "MQTT_BROKER is not ip:port"
+    );
+    let config = Config {
+        client_id: option_env!("MQTT_CLIENT_ID").unwrap_or("pleiades"),
+        base: option_env!("MQTT_BASE").unwrap_or("pleiades"),
+        username: option_env!("MQTT_USERNAME"),
+        password: option_env!("MQTT_PASSWORD"),
+        ..Config::default()
+    };
+    client::run(stack, broker, &config, &Remote).await
+}
+
+/// Writes the settings to flash, away from the main loop
+#[embassy_executor::task]
+async fn settings_task(mut settings: Settings) -> ! {
+    use embassy_futures::select::{select, Either};
+    use embassy_time::with_timeout;
+
+    loop {
+        match select(SAVE.wait(), SAVE_OFFSET.wait()).await {
+            Either::First(mut state) => {
+                while let Ok(newer) = with_timeout(SAVE_DELAY, SAVE.wait()).await {
+                    state = newer;
+                }
+                if let Err(err) = settings.save(&state) {
+                    defmt::error!("Failed to save the settings: {}", err);
+                }
+            }
+            Either::Second(offset) => {
+                if let Err(err) = settings.set_proximity_offset(offset) {
+                    defmt::error!("Failed to store the proximity offset: {}", err);
+                }
+            }
+        }
+    }
+}
+
+#[embassy_executor::task]
+async fn sensor_task(
+    mut apds: Apds9960<i2c::I2c<'static, I2C0, Async>>,
+    #[cfg_attr(
+        not(feature = "proximity-interrupt"),
+        allow(unused_variables, unused_mut)
+    )]
+    mut int: Input<'static, PIN_19>,
+    offset: Option<i8>,
+) -> ! {
+    // Main starts out with the sensor taken as present
+    let mut present = true;
+
+    let mut offset = offset.unwrap_or(0);
+    defmt::info!("Proximity offset: {}", offset);
+
+    loop {
+        // Probe the bus until the sensor answers
+        while let Err(err) = setup(&mut apds, offset).await {
+            if present {
+                defmt::error!("Gesture sensor not found: {}", err);
+                present = false;
+                SENSOR_PRESENT.store(false, Ordering::Relaxed);
+                SENSOR.send(Sensor::Missing).await;
+            }
+            Timer::after(RESCAN_PERIOD).await;
+        }
+        if !present {
+            defmt::info!("Gesture sensor found");
+            present = true;
+            SENSOR_PRESENT.store(true, Ordering::Relaxed);
+            SENSOR.send(Sensor::Present).await;
+        }
+
+        let mut ticker = Ticker::every(Duration::from_millis(10));
+        let mut errors = 0;
+
+        while errors < LOST_AFTER {
+            if let Ok(config) = GESTURE_CONFIG.try_receive() {
+                apds.set_gesture_config(config);
+                #[cfg(feature = "proximity-interrupt")]
+                if let Err(err) = apds.enable_proximity_interrupt().await {
+                    defmt::error!("Failed to update the proximity thresholds: {}", err);
+                }
+            }
+            if let Ok(config) = PROXIMITY_CONFIG.try_receive() {
+                if let Err(err) = apds.set_proximity_config(config).await {
+                    defmt::error!("Failed to update the proximity config: {}", err);
+                }
+            }
+            if CALIBRATE.try_receive().is_ok() {
+                match calibrate(&mut apds).await {
+                    Ok(calibrated) => {
+                        defmt::info!("Proximity offset calibrated: {}", calibrated);
+                        offset = calibrated;
+                        SAVE_OFFSET.signal(offset);
+                    }
+                    Err(err) => {
+                        defmt::error!("Calibration failed: {}", err);
+                        errors += 1;
+                    }
+                }
+                // Start over from the new offset
+                apds.set_gesture_config(apds.gesture_config());
+                ticker.reset();
+            }
+            // if let Ok(d) = apds.read().await {
+            //     defmt::info!("Dist: {}", d);
+            // }
+            match apds.gesture().await {
+                Ok(()) => {
+                    errors = 0;
+                    PROXIMITY.store(apds.proximity(), Ordering::Relaxed);
+                }
+                Err(err) => {
+                    defmt::error!("Gesture sensor read failed: {}", err);
+                    errors += 1;
+                }
+            }
+            if let Some(command) = apds.command() {
+                if let Err(_err) = CHANNEL.try_send(command) {
+                    defmt::error!("Command channel buffer is full");
+                }
+                // Nobody listens while the broker is away
+                #[cfg(feature = "mqtt")]
+                let _ = GESTURES.try_send(command);
+            }
+
+            // Nobody near: sleep until the proximity interrupt instead of polling.
+            // Wake up now and then to check the sensor is still there.
+            #[cfg(feature = "proximity-interrupt")]
+            if apds.is_idle() {
+                let hand =
+                    embassy_time::with_timeout(RESCAN_PERIOD, apds.wait_for_hand(&mut int)).await;
+                if let Ok(Err(err)) = hand {
+                    defmt::error!("Failed to clear the proximity interrupt: {}", err);
+                    errors += 1;
+                }
+                ticker.reset();
+            }
+            ticker.next().await;
+        }
+    }
+}
+
+/// Check the sensor is there and enable the features the firmware is built with
+async fn setup(
+    apds: &mut Apds9960<i2c::I2c<'static, I2C0, Async>>,
+    offset: i8,
+) -> Result<(), apds9960::Error<i2c::Error>> {
+    apds.check_id().await?;
+    apds.enable().await?;
+    apds.powerup().await?;
+    apds.set_proximity_offset(offset).await?;
+    #[cfg(feature = "gesture-engine")]
+    apds.enable_gesture_engine().await?;
+    #[cfg(feature = "proximity-interrupt")]
+    apds.enable_proximity_interrupt().await?;
+    Ok(())
+}
+
+/// Measure the idle baseline with offsets picked by `Calibration` and leave
+/// the sensor programmed with the one found. Nobody should be near the
+/// lantern meanwhile.
+async fn calibrate(
+    apds: &mut Apds9960<i2c::I2c<'static, I2C0, Async>>,
+) -> Result<i8, apds9960::Error<i2c::Error>> {
+    Timer::after(CALIBRATION_DELAY).await;
+
+    // Half the floor leaves room for noise before a hand is seen
+    let mut search = Calibration::new(apds.gesture_config().floor / 2);
+    while let Some(offset) = search.offset() {
+        apds.set_proximity_offset(offset).await?;
+
+        let mut samples = [0u8; CALIBRATION_SAMPLES];
+        for sample in samples.iter_mut() {
+            // Wait out the proximity cycle started before the new offset
+            *sample = loop {
+                Timer::after(SAMPLE_PERIOD).await;
+                match apds.read().await {
+                    Ok(dist) => break dist,
+                    Err(apds9960::Error::NotReady) => continue,
+                    Err(err) => return Err(err),
+                }
+            };
+        }
+        search.measured(calibration::baseline(&mut samples));
+    }
+
+    let offset = search.result();
+    apds.set_proximity_offset(offset).await?;
+    Ok(offset)
+}
@@ -0,0 +1,19 @@
+use embassy_rp::flash::{Blocking, Error, Flash, ERASE_SIZE};
+use embassy_rp::peripherals::FLASH;
+use pleiades_core::settings::{self, Store};
+
+/// Size of the flash chip on the Pico W
+pub const FLASH_SIZE: usize = 2 * 1024 * 1024;
+/// Sectors the settings take turns on, to spread the wear
+const SECTORS: usize = 4;
+/// Last sectors, kept out of the program by `memory.x`
+const SETTINGS_OFFSET: u32 = (FLASH_SIZE - SECTORS * ERASE_SIZE) as u32;
+
+/// Settings that survive a power cycle, see `pleiades_core::settings`.
+/// Writing stalls the whole chip, some tens of ms when a sector is erased.
+pub type Settings = settings::Settings<Flash<'static, FLASH, Blocking, FLASH_SIZE>>;
+
+pub fn open(flash: FLASH) -> Result<Settings, settings::Error<Error>> {
+    let store = Store::open(Flash::new_blocking(flash), SETTINGS_OFFSET, SECTORS as u32)?;
+    Ok(Settings::new(store))
+}
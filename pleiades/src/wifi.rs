@@ -0,0 +1,114 @@
+//! Wi-Fi of the Pico W. Joins the network given at build time through
+//! `WIFI_SSID` and `WIFI_PASSWORD` and gets an address over DHCP.
+
+use cyw43::{Control, NetDriver, PowerManagementMode, State};
+use cyw43_pio::PioSpi;
+use defmt::*;
+use embassy_executor::Spawner;
+use embassy_net::{Config, Stack, StackResources};
+use embassy_rp::bind_interrupts;
+use embassy_rp::clocks::RoscRng;
+use embassy_rp::gpio::{Level, Output};
+use embassy_rp::peripherals::{DMA_CH1, PIN_23, PIN_24, PIN_25, PIN_29, PIO1};
+use embassy_rp::pio::{InterruptHandler, Pio};
+use embassy_time::{Duration, Timer};
+use rand::RngCore;
+use static_cell::StaticCell;
+
+const SSID: &str = env!("WIFI_SSID");
+const PASSWORD: &str = env!("WIFI_PASSWORD");
+
+/// How often the link is checked, the network is joined again once lost
+const LINK_CHECK: Duration = Duration::from_secs(10);
+/// DHCP, the servers on top and the five stream receivers
+const SOCKETS: usize = 9;
+
+bind_interrupts!(struct Irqs {
+    PIO1_IRQ_0 => InterruptHandler<PIO1>;
+});
+
+pub type Device = NetDriver<'static>;
+type Spi = PioSpi<'static, PIN_25, PIO1, 0, DMA_CH1>;
+
+/// Bring the radio up and spawn the tasks that keep the network going.
+/// The radio lets through the IPv4 `multicast` groups, which the stack
+/// still has to join. The stack answers once DHCP is done, see
+/// `Stack::wait_config_up`.
+#[allow(clippy::too_many_arguments)]
+pub async fn start(
+    spawner: &Spawner,
+    pwr: PIN_23,
+    cs: PIN_25,
+    dio: PIN_24,
+    clk: PIN_29,
+    pio: PIO1,
+    dma: DMA_CH1,
+    multicast: impl IntoIterator<Item = [u8; 4]>,
+) -> &'static Stack<Device> {
+    let firmware = include_bytes!("../cyw43-firmware/43439A0.bin");
+    let clm = include_bytes!("../cyw43-firmware/43439A0_clm.bin");
+
+    let pwr = Output::new(pwr, Level::Low);
+    let cs = Output::new(cs, Level::High);
+    let mut pio = Pio::new(pio, Irqs);
+    let spi = PioSpi::new(&mut pio.common, pio.sm0, pio.irq0, cs, dio, clk, dma);
+
+    static STATE: StaticCell<State> = StaticCell::new();
+    let state = STATE.init(State::new());
+    let (device, mut control, runner) = cyw43::new(state, pwr, spi, firmware).await;
+    unwrap!(spawner.spawn(radio_task(runner)));
+
+    control.init(clm).await;
+    control
+        .set_power_management(PowerManagementMode::PowerSave)
+        .await;
+    for group in multicast {
+        if let Err(err) = control.add_multicast_address(multicast_mac(group)).await {
+            error!("Failed to let through {}: {}", group, Debug2Format(&err));
+        }
+    }
+
+    static RESOURCES: StaticCell<StackResources<SOCKETS>> = StaticCell::new();
+    static STACK: StaticCell<Stack<Device>> = StaticCell::new();
+    let stack = &*STACK.init(Stack::new(
+        device,
+        Config::dhcpv4(Default::default()),
+        RESOURCES.init(StackResources::new()),
+        RoscRng.next_u64(),
+    ));
+    unwrap!(spawner.spawn(net_task(stack)));
+    unwrap!(spawner.spawn(join_task(control, stack)));
+    stack
+}
+
+/// Ethernet address of an IPv4 multicast group, 01:00:5e and its low
+/// 23 bits
+fn multicast_mac(group: [u8; 4]) -> [u8; 6] {
+    [0x01, 0x00, 0x5E, group[1] & 0x7F, group[2], group[3]]
+}
+
+#[embassy_executor::task]
+async fn radio_task(runner: cyw43::Runner<'static, Output<'static, PIN_23>, Spi>) -> ! {
+    runner.run().await
+}
+
+#[embassy_executor::task]
+async fn net_task(stack: &'static Stack<Device>) -> ! {
+    stack.run().await
+}
+
+#[embassy_executor::task]
+async fn join_task(mut control: Control<'static>, stack: &'static Stack<Device>) -> ! {
+    loop {
+        if !stack.is_link_up() {
+            match control.join_wpa2(SSID, PASSWORD).await {
+                Ok(()) => info!("Joined {}", SSID),
+                Err(err) => error!("Failed to join {}: status {}", SSID, err.status),
+            }
+        }
+        if let Some(config) = stack.config_v4() {
+            debug!("Address: {}", config.address);
+        }
+        Timer::after(LINK_CHECK).await;
+    }
+}
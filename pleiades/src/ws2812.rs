@@ -5,12 +5,9 @@ use embassy_rp::pio::{
 use embassy_rp::{clocks, into_ref, Peripheral, PeripheralRef};
 use fixed::types::U24F8;
 use fixed_macro::fixed;
+use pleiades_core::led_matrix::PioWrite;
 use smart_leds::RGB8;
 
-pub trait PioWrite<const N: usize> {
-    async fn write(&mut self, colors: &[RGB8; N]);
-}
-
 pub struct Ws2812<'d, P: Instance, const S: usize, const N: usize> {
     dma: PeripheralRef<'d, AnyChannel>,
     sm: StateMachine<'d, P, S>,
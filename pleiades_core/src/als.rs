@@ -0,0 +1,118 @@
+/// Length of one ALS integration cycle, in ms
+const CYCLE_MS: f32 = 2.78;
+
+// Lux and color temperature coefficients for the sensor in open air
+const DEVICE_FACTOR: f32 = 412.0;
+const GLASS_ATTENUATION: f32 = 1.0;
+const RED_COEF: f32 = 0.136;
+const GREEN_COEF: f32 = 1.0;
+const BLUE_COEF: f32 = -0.444;
+const CT_COEF: f32 = 3810.0;
+const CT_OFFSET: f32 = 1391.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum AlsGain {
+    X1,
+    #[default]
+    X4,
+    X16,
+    X64,
+}
+
+impl AlsGain {
+    /// `AGAIN` field of the `CONTROL` register
+    pub fn bits(&self) -> u8 {
+        match self {
+            AlsGain::X1 => 0b00,
+            AlsGain::X4 => 0b01,
+            AlsGain::X16 => 0b10,
+            AlsGain::X64 => 0b11,
+        }
+    }
+
+    pub fn factor(&self) -> f32 {
+        match self {
+            AlsGain::X1 => 1.0,
+            AlsGain::X4 => 4.0,
+            AlsGain::X16 => 16.0,
+            AlsGain::X64 => 64.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AlsConfig {
+    /// Integration cycles, from 1 to 256
+    pub cycles: u16,
+    pub gain: AlsGain,
+}
+
+impl Default for AlsConfig {
+    fn default() -> Self {
+        // 200 ms
+        Self {
+            cycles: 72,
+            gain: AlsGain::X4,
+        }
+    }
+}
+
+impl AlsConfig {
+    /// Value of the `ATIME` register
+    pub fn atime(&self) -> u8 {
+        (256 - self.cycles.clamp(1, 256)) as u8
+    }
+
+    pub fn integration_ms(&self) -> f32 {
+        self.cycles.clamp(1, 256) as f32 * CYCLE_MS
+    }
+
+    /// Highest count a channel reaches before it saturates
+    pub fn max_count(&self) -> u16 {
+        (self.cycles.clamp(1, 256) as u32 * 1025).min(u16::MAX as u32) as u16
+    }
+}
+
+/// One ambient light reading
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Light {
+    pub clear: u16,
+    pub red: u16,
+    pub green: u16,
+    pub blue: u16,
+    /// Illuminance, 0 when the clear channel saturates
+    pub lux: f32,
+    /// Correlated color temperature in K, 0 when there is too little
+    /// red to tell
+    pub cct: f32,
+}
+
+impl Light {
+    pub fn new(clear: u16, red: u16, green: u16, blue: u16, config: &AlsConfig) -> Self {
+        let mut light = Light {
+            clear,
+            red,
+            green,
+            blue,
+            ..Default::default()
+        };
+        if clear >= config.max_count() {
+            return light;
+        }
+
+        // Remove the infrared part seen by every channel
+        let (c, r, g, b) = (clear as f32, red as f32, green as f32, blue as f32);
+        let ir = ((r + g + b - c) / 2.0).max(0.0);
+        let (r, g, b) = (r - ir, g - ir, b - ir);
+
+        // Counts per lux
+        let cpl =
+            config.integration_ms() * config.gain.factor() / (GLASS_ATTENUATION * DEVICE_FACTOR);
+        light.lux = ((RED_COEF * r + GREEN_COEF * g + BLUE_COEF * b) / cpl).max(0.0);
+
+        if r > 0.0 {
+            light.cct = CT_COEF * b / r + CT_OFFSET;
+        }
+        light
+    }
+}
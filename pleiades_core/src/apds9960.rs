@@ -0,0 +1,435 @@
+#![allow(dead_code)]
+use crate::als::{AlsConfig, Light};
+use crate::calibration;
+use crate::gesture::{Command, GestureConfig, StateMashine};
+use crate::proximity::{ConfigError, ProximityConfig};
+use crate::swipe::{Dataset, SwipeRecognizer};
+use core::option::Option;
+use embassy_time::{with_timeout, Duration};
+use embedded_hal_async::digital::Wait;
+use embedded_hal_async::i2c::I2c;
+
+pub const DEV_ADDR: u8 = 0x39;
+
+pub struct Register;
+impl Register {
+    const ENABLE: u8 = 0x80;
+    const ATIME: u8 = 0x81;
+    const WTIME: u8 = 0x83;
+    const AILTL: u8 = 0x84;
+    const AIHTL: u8 = 0x86;
+    const PILT: u8 = 0x89;
+    const PIHT: u8 = 0x8B;
+    const PERS: u8 = 0x8C;
+    const CONFIG1: u8 = 0x8D;
+    const PPULSE: u8 = 0x8E;
+    const CONTROL: u8 = 0x8F;
+    const CONFIG2: u8 = 0x90;
+    const ID: u8 = 0x92;
+    const STATUS: u8 = 0x93;
+    const CDATAL: u8 = 0x94;
+    const RDATAL: u8 = 0x96;
+    const GDATAL: u8 = 0x98;
+    const BDATAL: u8 = 0x9A;
+    const PDATA: u8 = 0x9C;
+    const POFFSET_UR: u8 = 0x9D;
+    const POFFSET_DL: u8 = 0x9E;
+    const GPENTH: u8 = 0xA0;
+    const GPEXTH: u8 = 0xA1;
+    const GCONFIG1: u8 = 0xA2;
+    const GCONFIG2: u8 = 0xA3;
+    const GOFFSET_U: u8 = 0xA4;
+    const GOFFSET_D: u8 = 0xA5;
+    const GOFFSET_L: u8 = 0xA6;
+    const GOFFSET_R: u8 = 0xA7;
+    const GCONFIG4: u8 = 0xAB;
+    const GFLVL: u8 = 0xAE;
+    const GSTATUS: u8 = 0xAF;
+    const IFORCE: u8 = 0xE4;
+    const PICLEAR: u8 = 0xE5;
+    const CICLEAR: u8 = 0xE6;
+    const AICLEAR: u8 = 0xE7;
+    const GFIFO_U: u8 = 0xFC;
+}
+
+pub struct Enable(u8);
+impl Enable {
+    const ALL: u8 = 0b1111_1111;
+    const PON: u8 = 0b0000_0001;
+    const AEN: u8 = 0b0000_0010;
+    const PEN: u8 = 0b0000_0100;
+    const WEN: u8 = 0b0000_1000;
+    const AIEN: u8 = 0b0001_0000;
+    const PIEN: u8 = 0b0010_0000;
+    const GEN: u8 = 0b0100_0000;
+}
+
+pub struct Status(u8);
+impl Status {
+    pub const AVALID: u8 = 0b0000_0001;
+    pub const PVALID: u8 = 0b0000_0010;
+}
+
+pub struct GStatus(u8);
+impl GStatus {
+    pub const GVALID: u8 = 0b0000_0001;
+    pub const GFOV: u8 = 0b0000_0010;
+}
+
+pub struct GConfig4(u8);
+impl GConfig4 {
+    pub const GMODE: u8 = 0b0000_0001;
+    pub const GIEN: u8 = 0b0000_0010;
+}
+
+/// Datasets the gesture FIFO holds
+const GFIFO_SIZE: usize = 32;
+
+/// Value of the `ID` register, some batches report the alternative one
+const ID: u8 = 0xAB;
+const ID_ALT: u8 = 0xA8;
+
+/// Longest a bus transaction may take before the sensor is given up on
+const BUS_TIMEOUT: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    /// I2C transaction failed
+    Bus(E),
+    /// Sensor has not completed a measurement since the last read
+    NotReady,
+    /// Device at `DEV_ADDR` is not an APDS-9960, holds the `ID` it reported
+    WrongId(u8),
+    /// Bus transaction did not complete in `BUS_TIMEOUT`
+    Timeout,
+    /// Configuration out of the datasheet's range, nothing was written
+    Config(ConfigError),
+}
+
+#[cfg(feature = "defmt")]
+impl<E: defmt::Format> defmt::Format for Error<E> {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            Error::Bus(err) => defmt::write!(fmt, "Bus({})", err),
+            Error::NotReady => defmt::write!(fmt, "NotReady"),
+            Error::WrongId(id) => defmt::write!(fmt, "WrongId({=u8:#x})", id),
+            Error::Timeout => defmt::write!(fmt, "Timeout"),
+            Error::Config(err) => defmt::write!(fmt, "Config({})", err),
+        }
+    }
+}
+
+pub struct Apds9960<I: I2c> {
+    i2c: I,
+    sm: StateMashine,
+    swipes: SwipeRecognizer,
+    als: AlsConfig,
+    proximity: ProximityConfig,
+    // Latest `PDATA` reading
+    pdata: u8,
+    // Gesture engine is enabled
+    engine: bool,
+    // Gesture engine has filled the FIFO since it entered gesture mode
+    swiping: bool,
+}
+
+impl<I: I2c> Apds9960<I> {
+    pub fn new(i2c: I, config: GestureConfig) -> Self {
+        let sm = StateMashine::new(config);
+        Apds9960 {
+            i2c,
+            sm,
+            swipes: SwipeRecognizer::default(),
+            als: AlsConfig::default(),
+            proximity: ProximityConfig::default(),
+            pdata: 0,
+            engine: false,
+            swiping: false,
+        }
+    }
+
+    /// Give the bus back
+    pub fn release(self) -> I {
+        self.i2c
+    }
+
+    async fn write(&mut self, bytes: &[u8]) -> Result<(), Error<I::Error>> {
+        with_timeout(BUS_TIMEOUT, self.i2c.write(DEV_ADDR, bytes))
+            .await
+            .map_err(|_| Error::Timeout)?
+            .map_err(Error::Bus)
+    }
+
+    async fn write_read(&mut self, register: u8, buffer: &mut [u8]) -> Result<(), Error<I::Error>> {
+        with_timeout(
+            BUS_TIMEOUT,
+            self.i2c.write_read(DEV_ADDR, &[register], buffer),
+        )
+        .await
+        .map_err(|_| Error::Timeout)?
+        .map_err(Error::Bus)
+    }
+
+    /// Make sure an APDS-9960 answers at `DEV_ADDR`
+    pub async fn check_id(&mut self) -> Result<(), Error<I::Error>> {
+        let mut id = [0u8];
+        self.write_read(Register::ID, &mut id).await?;
+        match id[0] {
+            ID | ID_ALT => Ok(()),
+            id => Err(Error::WrongId(id)),
+        }
+    }
+
+    pub async fn enable(&mut self) -> Result<(), Error<I::Error>> {
+        self.write(&[Register::ENABLE, Enable::PON | Enable::PEN])
+            .await?;
+        Ok(())
+    }
+
+    /// Apply the proximity config, the default one until
+    /// `set_proximity_config`. Call after `enable`, which overwrites the
+    /// whole `ENABLE` register.
+    pub async fn powerup(&mut self) -> Result<(), Error<I::Error>> {
+        let config = self.proximity;
+
+        let mut control = [0u8];
+        self.write_read(Register::CONTROL, &mut control).await?;
+        self.write(&[
+            Register::CONTROL,
+            config.led_drive.bits() << 6 | config.gain.bits() << 2 | control[0] & 0b11,
+        ])
+        .await?; // LDRIVE, PGAIN, keeps AGAIN
+
+        self.write(&[Register::CONFIG2, config.led_boost.bits() << 4 | 0b1])
+            .await?; // LED_BOOST, the reserved bit 0 is written as 1
+
+        self.write(&[Register::PPULSE, config.ppulse()]).await?;
+
+        match config.wait {
+            Some(wait) => {
+                self.write(&[Register::WTIME, wait.wtime()]).await?;
+                self.write(&[Register::CONFIG1, 0b0110_0000 | (wait.long as u8) << 1])
+                    .await?; // WLONG, the reserved bits are written as 0x60
+                self.set_enable(Enable::PON | Enable::WEN).await
+            }
+            None => self.clear_enable(Enable::WEN).await,
+        }
+    }
+
+    /// Check `config` against the datasheet and apply it, it stays in use
+    /// when `powerup` runs again
+    pub async fn set_proximity_config(
+        &mut self,
+        config: ProximityConfig,
+    ) -> Result<(), Error<I::Error>> {
+        config.validate().map_err(Error::Config)?;
+        self.proximity = config;
+        self.powerup().await
+    }
+
+    pub fn proximity_config(&self) -> ProximityConfig {
+        self.proximity
+    }
+
+    /// Latest proximity reading, `NotReady` until a proximity cycle has completed
+    pub async fn read(&mut self) -> Result<u8, Error<I::Error>> {
+        let mut is_prox = [0u8];
+        self.write_read(Register::STATUS, &mut is_prox).await?;
+
+        let mut prox = [0u8];
+        if is_prox[0] & Status::PVALID != 0 {
+            self.write_read(Register::PDATA, &mut prox).await?;
+
+            // Capture for the host replayer, see `pleiades_sim::trace`
+            #[cfg(feature = "trace")]
+            defmt::println!(
+                "pdata {=u64} {=u8}",
+                embassy_time::Instant::now().as_millis(),
+                prox[0]
+            );
+
+            self.pdata = prox[0];
+            return Ok(prox[0]);
+        }
+        Err(Error::NotReady)
+    }
+
+    /// Latest reading returned by `read`
+    pub fn proximity(&self) -> u8 {
+        self.pdata
+    }
+
+    /// Set `bits` in `ENABLE`, keeping the functions already enabled
+    async fn set_enable(&mut self, bits: u8) -> Result<(), Error<I::Error>> {
+        let mut enable = [0u8];
+        self.write_read(Register::ENABLE, &mut enable).await?;
+        self.write(&[Register::ENABLE, enable[0] | bits]).await?;
+        Ok(())
+    }
+
+    /// Clear `bits` in `ENABLE`, keeping the other functions enabled
+    async fn clear_enable(&mut self, bits: u8) -> Result<(), Error<I::Error>> {
+        let mut enable = [0u8];
+        self.write_read(Register::ENABLE, &mut enable).await?;
+        self.write(&[Register::ENABLE, enable[0] & !bits]).await?;
+        Ok(())
+    }
+
+    /// Assert INT once `PDATA` rises above the floor of the gesture
+    /// config. Call again after changing the config.
+    pub async fn enable_proximity_interrupt(&mut self) -> Result<(), Error<I::Error>> {
+        self.write(&[Register::PILT, 0]).await?;
+        self.write(&[Register::PIHT, self.sm.config().floor])
+            .await?;
+
+        self.write(&[Register::PERS, 0b0001_0000]).await?; // PPERS, any reading out of the thresholds
+
+        self.set_enable(Enable::PON | Enable::PEN | Enable::PIEN)
+            .await
+    }
+
+    /// Sleep until a hand shows up above the sensor. INT is active low and
+    /// stays asserted until `PICLEAR`, so a hand that came in meanwhile
+    /// wakes us up right away.
+    pub async fn wait_for_hand(&mut self, int: &mut impl Wait) -> Result<(), Error<I::Error>> {
+        self.write(&[Register::PICLEAR]).await?;
+        // GPIO inputs are infallible
+        let _ = int.wait_for_low().await;
+        Ok(())
+    }
+
+    /// Program the offset the sensor subtracts from the proximity counts of
+    /// both photodiode pairs, see `calibration`
+    pub async fn set_proximity_offset(&mut self, offset: i8) -> Result<(), Error<I::Error>> {
+        let poffset = calibration::poffset(offset);
+        self.write(&[Register::POFFSET_UR, poffset]).await?;
+        self.write(&[Register::POFFSET_DL, poffset]).await
+    }
+
+    /// No hand above the sensor and no gesture in progress
+    pub fn is_idle(&self) -> bool {
+        !self.swiping && self.sm.is_idle()
+    }
+
+    /// Start measuring ambient light next to proximity. Call after `enable`,
+    /// which overwrites the whole `ENABLE` register.
+    pub async fn enable_als(&mut self, config: AlsConfig) -> Result<(), Error<I::Error>> {
+        self.write(&[Register::ATIME, config.atime()]).await?;
+
+        let mut control = [0u8];
+        self.write_read(Register::CONTROL, &mut control).await?;
+        self.write(&[Register::CONTROL, control[0] & !0b11 | config.gain.bits()])
+            .await?; // AGAIN
+
+        self.set_enable(Enable::PON | Enable::AEN).await?;
+        self.als = config;
+        Ok(())
+    }
+
+    /// Latest ambient light reading, `NotReady` until an integration cycle
+    /// has completed
+    pub async fn light(&mut self) -> Result<Light, Error<I::Error>> {
+        let mut status = [0u8];
+        self.write_read(Register::STATUS, &mut status).await?;
+        if status[0] & Status::AVALID == 0 {
+            return Err(Error::NotReady);
+        }
+
+        // CDATA, RDATA, GDATA and BDATA, low byte first
+        let mut data = [0u8; 8];
+        self.write_read(Register::CDATAL, &mut data).await?;
+        let count = |i: usize| u16::from_le_bytes([data[i], data[i + 1]]);
+
+        Ok(Light::new(
+            count(0),
+            count(2),
+            count(4),
+            count(6),
+            &self.als,
+        ))
+    }
+
+    /// Hand the photodiodes over to the gesture engine whenever `PDATA`
+    /// crosses `GPENTH`. Proximity readings pause during a gesture, so
+    /// swings and levels give way to swipes.
+    pub async fn enable_gesture_engine(&mut self) -> Result<(), Error<I::Error>> {
+        self.write(&[Register::GPENTH, 40]).await?; // Enter
+        self.write(&[Register::GPEXTH, 30]).await?; // Exit
+
+        self.write(&[Register::GCONFIG1, 0b0100_0001]).await?; // 4 datasets per FIFO level, exit after 2 low datasets
+
+        self.write(&[Register::GCONFIG2, 0b0100_0001]).await?; // GAIN 4x, LED DRIVE 100 mA, WAIT 2.8 ms
+
+        self.write(&[Register::GCONFIG4, 0]).await?; // No interrupt
+
+        self.set_enable(Enable::PON | Enable::PEN | Enable::GEN)
+            .await?;
+        self.engine = true;
+        Ok(())
+    }
+
+    /// Drain the gesture FIFO into the swipe recognizer
+    pub async fn swipe(&mut self) -> Result<(), Error<I::Error>> {
+        let mut level = [0u8];
+        self.write_read(Register::GFLVL, &mut level).await?;
+
+        let datasets = (level[0] as usize).min(GFIFO_SIZE);
+        if datasets > 0 {
+            // The FIFO address auto-increments over U, D, L and R
+            let mut fifo = [0u8; GFIFO_SIZE * 4];
+            let fifo = &mut fifo[..datasets * 4];
+            self.write_read(Register::GFIFO_U, fifo).await?;
+
+            for dataset in fifo.chunks_exact(4) {
+                self.swipes.next(Dataset {
+                    up: dataset[0],
+                    down: dataset[1],
+                    left: dataset[2],
+                    right: dataset[3],
+                });
+            }
+            self.swiping = true;
+            return Ok(());
+        }
+
+        let mut config4 = [0u8];
+        self.write_read(Register::GCONFIG4, &mut config4).await?;
+
+        // Hand is gone and the FIFO is empty
+        if self.swiping && config4[0] & GConfig4::GMODE == 0 {
+            self.swipes.end();
+            self.swiping = false;
+        }
+        Ok(())
+    }
+
+    /// Feed the latest reading to the recognizer of the current mode. With
+    /// the gesture engine `PDATA` stands still whenever a hand is above the
+    /// sensor, so only the swipe recognizer runs, a hand held close gives
+    /// `Near` and `Far`.
+    pub async fn gesture(&mut self) -> Result<(), Error<I::Error>> {
+        match self.engine {
+            true => self.swipe().await,
+            false => match self.read().await {
+                Ok(dist) => {
+                    self.sm.next(dist);
+                    Ok(())
+                }
+                Err(Error::NotReady) => Ok(()),
+                Err(err) => Err(err),
+            },
+        }
+    }
+
+    pub fn command(&mut self) -> Option<Command> {
+        self.sm.command().or_else(|| self.swipes.command())
+    }
+
+    pub fn gesture_config(&self) -> GestureConfig {
+        self.sm.config()
+    }
+
+    pub fn set_gesture_config(&mut self, config: GestureConfig) {
+        self.sm.set_config(config);
+    }
+}
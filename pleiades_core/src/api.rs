@@ -0,0 +1,295 @@
+//! HTTP/JSON API of the lantern. Requests turn into the same `Command`s
+//! the gestures send, `server` answers them over an embassy-net stack.
+//!
+//! - `GET /api/status`: `{"world":"fire","on":true,"brightness":255}`
+//! - `GET /api/worlds`: `{"worlds":["fire",...],"current":"fire"}`
+//! - `PUT /api/world` with `{"world":"matrix"}`
+//! - `POST /api/parameter` with `{"direction":"up"}` or `"down"`
+//! - `PUT /api/brightness` with `{"brightness":128}`
+//! - `PUT /api/power` with `{"on":false}`
+//!
+//! Changes answer with the status the lantern is about to show. `wled`
+//! answers the `/json` paths of the WLED API on the same server.
+
+#[cfg(feature = "net")]
+pub mod server;
+pub mod wled;
+
+use crate::gesture::{Command, Direction};
+use crate::world::NAMES;
+use core::fmt::Write;
+use heapless::String;
+use serde::{Deserialize, Serialize};
+
+/// Largest response body, WLED's `/json` is the longest
+pub const BODY_SIZE: usize = 1024;
+/// Largest status line and headers of a response
+pub const HEAD_SIZE: usize = 128;
+
+/// What a remote sees of the lantern
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Status {
+    /// Index in `world::NAMES`
+    pub world: usize,
+    pub on: bool,
+    pub brightness: u8,
+}
+
+/// The lantern as the remotes drive it
+pub trait Lantern {
+    fn status(&self) -> Status;
+    /// Queue `command` for the main loop
+    async fn send(&self, command: Command);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Put,
+    Post,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// Headers or body not fully received yet
+    Incomplete,
+    /// Not an HTTP/1.x request
+    Invalid,
+}
+
+/// An HTTP request with its whole body
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Request<'a> {
+    pub method: Method,
+    /// Without the query string
+    pub path: &'a str,
+    pub body: &'a [u8],
+}
+
+impl<'a> Request<'a> {
+    pub fn parse(buffer: &'a [u8]) -> Result<Self, ParseError> {
+        let end = buffer
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .ok_or(ParseError::Incomplete)?;
+        let head = core::str::from_utf8(&buffer[..end]).map_err(|_| ParseError::Invalid)?;
+        let mut lines = head.split("\r\n");
+
+        let mut request_line = lines.next().unwrap_or_default().split(' ');
+        let method = match request_line.next() {
+            Some("GET") => Method::Get,
+            Some("PUT") => Method::Put,
+            Some("POST") => Method::Post,
+            Some(_) => Method::Other,
+            None => return Err(ParseError::Invalid),
+        };
+        let target = request_line.next().ok_or(ParseError::Invalid)?;
+        let path = target.split('?').next().unwrap_or_default();
+        match request_line.next() {
+            Some(version) if version.starts_with("HTTP/1.") => {}
+            _ => return Err(ParseError::Invalid),
+        }
+
+        let mut length = 0;
+        for line in lines {
+            if let Some((name, value)) = line.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().map_err(|_| ParseError::Invalid)?;
+                }
+            }
+        }
+
+        let body = &buffer[end + 4..];
+        if body.len() < length {
+            return Err(ParseError::Incomplete);
+        }
+        Ok(Request {
+            method,
+            path,
+            body: &body[..length],
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    /// JSON
+    pub body: String<BODY_SIZE>,
+}
+
+impl Response {
+    pub fn json(status: u16, value: &impl Serialize) -> Self {
+        match serde_json_core::to_string(value) {
+            Ok(body) => Response { status, body },
+            Err(_) => Response {
+                status: 500,
+                body: String::new(),
+            },
+        }
+    }
+
+    pub fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &ErrorBody { error: message })
+    }
+
+    pub fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Payload Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+
+    /// Status line and headers, the connection closes after the body
+    pub fn head(&self) -> String<HEAD_SIZE> {
+        let mut head = String::new();
+        // Fits, the longest reason is short
+        let _ = write!(
+            head,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.reason(),
+            self.body.len()
+        );
+        head
+    }
+}
+
+#[derive(Serialize)]
+struct ErrorBody<'a> {
+    error: &'a str,
+}
+
+#[derive(Serialize)]
+struct StatusBody {
+    world: &'static str,
+    on: bool,
+    brightness: u8,
+}
+
+impl From<Status> for StatusBody {
+    fn from(status: Status) -> Self {
+        StatusBody {
+            world: NAMES.get(status.world).copied().unwrap_or_default(),
+            on: status.on,
+            brightness: status.brightness,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct WorldsBody {
+    worlds: &'static [&'static str],
+    current: &'static str,
+}
+
+#[derive(Deserialize)]
+struct WorldBody<'a> {
+    world: &'a str,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Step {
+    Up,
+    Down,
+}
+
+#[derive(Deserialize)]
+struct ParameterBody {
+    direction: Step,
+}
+
+#[derive(Deserialize)]
+struct BrightnessBody {
+    brightness: u8,
+}
+
+#[derive(Deserialize)]
+struct PowerBody {
+    on: bool,
+}
+
+/// Answer `request` given the current `status`, with the command that
+/// carries it out
+pub fn handle(request: &Request, status: Status) -> (Response, Option<Command>) {
+    let ok = |status: Status| Response::json(200, &StatusBody::from(status));
+
+    match (request.path, request.method) {
+        ("/api/status", Method::Get) => (ok(status), None),
+        ("/api/worlds", Method::Get) => {
+            let worlds = WorldsBody {
+                worlds: &NAMES,
+                current: StatusBody::from(status).world,
+            };
+            (Response::json(200, &worlds), None)
+        }
+        ("/api/world", Method::Put) => match parse::<WorldBody>(request.body) {
+            Some(body) => match NAMES.iter().position(|name| *name == body.world) {
+                Some(world) => (
+                    ok(Status {
+                        world,
+                        on: true,
+                        ..status
+                    }),
+                    Some(Command::World(world)),
+                ),
+                None => (Response::error(404, "Unknown world"), None),
+            },
+            None => (Response::error(400, "Expected {\"world\":name}"), None),
+        },
+        ("/api/parameter", Method::Post) => match parse::<ParameterBody>(request.body) {
+            Some(body) => {
+                let direction = match body.direction {
+                    Step::Up => Direction::Up,
+                    Step::Down => Direction::Down,
+                };
+                (ok(status), Some(Command::Level(direction)))
+            }
+            None => (
+                Response::error(400, "Expected {\"direction\":\"up\"|\"down\"}"),
+                None,
+            ),
+        },
+        ("/api/brightness", Method::Put) => match parse::<BrightnessBody>(request.body) {
+            Some(body) => (
+                ok(Status {
+                    brightness: body.brightness,
+                    ..status
+                }),
+                Some(Command::Brightness(body.brightness)),
+            ),
+            None => (
+                Response::error(400, "Expected {\"brightness\":0..255}"),
+                None,
+            ),
+        },
+        ("/api/power", Method::Put) => match parse::<PowerBody>(request.body) {
+            Some(body) => (
+                ok(Status {
+                    on: body.on,
+                    ..status
+                }),
+                Some(Command::Power(body.on)),
+            ),
+            None => (Response::error(400, "Expected {\"on\":bool}"), None),
+        },
+        (
+            "/api/status" | "/api/worlds" | "/api/world" | "/api/parameter" | "/api/brightness"
+            | "/api/power",
+            _,
+        ) => (Response::error(405, "Method not allowed"), None),
+        _ => (Response::error(404, "Not found"), None),
+    }
+}
+
+fn parse<'a, T: Deserialize<'a>>(body: &'a [u8]) -> Option<T> {
+    serde_json_core::from_slice(body)
+        .ok()
+        .map(|(value, _)| value)
+}
@@ -0,0 +1,94 @@
+//! Serves the API and the WLED API over TCP, one connection at a time
+
+use super::wled::{self, Commands, Device};
+use super::{handle, Lantern, ParseError, Request, Response, BODY_SIZE, HEAD_SIZE};
+use embassy_net::driver::Driver;
+use embassy_net::tcp::{Error, TcpSocket};
+use embassy_net::{HardwareAddress, Stack};
+use embassy_time::Duration;
+
+pub const PORT: u16 = 80;
+/// Largest request, headers included
+const REQUEST_SIZE: usize = 1024;
+/// Idle connections are dropped after
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// `leds` on the matrix, for the WLED apps
+pub async fn serve<D: Driver>(stack: &Stack<D>, port: u16, lantern: &impl Lantern, leds: u16) -> ! {
+    let mac = match stack.hardware_address() {
+        HardwareAddress::Ethernet(address) => address.0,
+        #[allow(unreachable_patterns)]
+        _ => [0; 6],
+    };
+    let device = Device {
+        name: wled::NAME,
+        leds,
+        mac,
+    };
+    let mut rx = [0u8; REQUEST_SIZE];
+    let mut tx = [0u8; HEAD_SIZE + BODY_SIZE];
+    let mut request = [0u8; REQUEST_SIZE];
+
+    loop {
+        let mut socket = TcpSocket::new(stack, &mut rx, &mut tx);
+        socket.set_timeout(Some(TIMEOUT));
+        if let Err(err) = socket.accept(port).await {
+            error!("API accept failed: {:?}", err);
+            continue;
+        }
+
+        if let Err(err) = respond(&mut socket, &mut request, lantern, &device).await {
+            error!("API connection failed: {:?}", err);
+        }
+        socket.close();
+        let _ = socket.flush().await;
+    }
+}
+
+async fn respond(
+    socket: &mut TcpSocket<'_>,
+    buffer: &mut [u8],
+    lantern: &impl Lantern,
+    device: &Device<'_>,
+) -> Result<(), Error> {
+    let mut len = 0;
+    let response = loop {
+        if len == buffer.len() {
+            break Response::error(413, "Request too large");
+        }
+        let read = socket.read(&mut buffer[len..]).await?;
+        if read == 0 {
+            // Closed before a whole request
+            return Ok(());
+        }
+        len += read;
+
+        match Request::parse(&buffer[..len]) {
+            Ok(request) => {
+                let (response, commands) = if request.path.starts_with("/json") {
+                    wled::handle(&request, lantern.status(), device)
+                } else {
+                    let (response, command) = handle(&request, lantern.status());
+                    (response, command.into_iter().collect::<Commands>())
+                };
+                for command in commands {
+                    lantern.send(command).await;
+                }
+                break response;
+            }
+            Err(ParseError::Incomplete) => continue,
+            Err(ParseError::Invalid) => break Response::error(400, "Invalid request"),
+        }
+    };
+
+    write_all(socket, response.head().as_bytes()).await?;
+    write_all(socket, response.body.as_bytes()).await
+}
+
+async fn write_all(socket: &mut TcpSocket<'_>, mut bytes: &[u8]) -> Result<(), Error> {
+    while !bytes.is_empty() {
+        let written = socket.write(bytes).await?;
+        bytes = &bytes[written..];
+    }
+    Ok(())
+}
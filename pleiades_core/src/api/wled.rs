@@ -0,0 +1,351 @@
+//! Core of the WLED JSON API, so the WLED apps and integrations drive the
+//! lantern. The worlds of `world::NAMES` are the effects, on a single
+//! segment covering every LED.
+//!
+//! - `GET /json/state`, `/json/info`, `/json/effects`, `/json/palettes`
+//! - `GET /json/si` for the state and info, `GET /json` for all of them
+//! - `POST /json/state` (or `/json/si`, `/json`) with
+//!   `{"on":true,"bri":128,"seg":[{"fx":2}]}`, any of them. `"on"` takes
+//!   `"t"` to toggle, `"bri":0` switches off and `"seg"` may be a single
+//!   segment. Answers `{"success":true}`, or the new state with `"v":true`.
+
+use super::{Method, Request, Response, Status};
+use crate::gesture::Command;
+use crate::world::{stream, NAMES};
+use core::fmt::{self, Write};
+use heapless::{String, Vec};
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{self, MapAccess, SeqAccess};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Name the apps show
+pub const NAME: &str = "Pleiades";
+/// WLED release whose API this follows
+const VERSION: &str = "0.14.0";
+const VERSION_ID: u32 = 2310130;
+const PALETTES: [&str; 1] = ["Default"];
+
+/// Commands carrying out a state change, in order
+pub type Commands = Vec<Command, 3>;
+
+/// What `/json/info` tells about the lantern
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Device<'a> {
+    pub name: &'a str,
+    pub leds: u16,
+    pub mac: [u8; 6],
+}
+
+#[derive(Serialize)]
+struct Segment {
+    id: u8,
+    start: u16,
+    stop: u16,
+    len: u16,
+    on: bool,
+    bri: u8,
+    col: [[u8; 3]; 3],
+    fx: usize,
+    sx: u8,
+    ix: u8,
+    pal: u8,
+    sel: bool,
+}
+
+#[derive(Serialize)]
+struct State {
+    on: bool,
+    bri: u8,
+    transition: u8,
+    ps: i8,
+    pl: i8,
+    mainseg: u8,
+    seg: [Segment; 1],
+}
+
+impl State {
+    fn new(status: Status, device: &Device) -> Self {
+        State {
+            on: status.on,
+            bri: status.brightness,
+            transition: 0,
+            ps: -1,
+            pl: -1,
+            mainseg: 0,
+            seg: [Segment {
+                id: 0,
+                start: 0,
+                stop: device.leds,
+                len: device.leds,
+                on: status.on,
+                bri: u8::MAX,
+                // The worlds pick their own colors
+                col: [[u8::MAX; 3], [0; 3], [0; 3]],
+                fx: status.world,
+                sx: 128,
+                ix: 128,
+                pal: 0,
+                sel: true,
+            }],
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Leds {
+    count: u16,
+    rgbw: bool,
+    wv: bool,
+    cct: bool,
+    pwr: u16,
+    fps: u8,
+    maxpwr: u16,
+    maxseg: u8,
+    seglc: [u8; 1],
+    lc: u8,
+}
+
+#[derive(Serialize)]
+struct Info<'a> {
+    ver: &'static str,
+    vid: u32,
+    leds: Leds,
+    str: bool,
+    name: &'a str,
+    udpport: u16,
+    live: bool,
+    fxcount: usize,
+    palcount: usize,
+    arch: &'static str,
+    brand: &'static str,
+    product: &'static str,
+    mac: String<12>,
+}
+
+impl<'a> Info<'a> {
+    fn new(device: &Device<'a>) -> Self {
+        let mut mac = String::new();
+        for byte in device.mac {
+            // Fits, two digits per byte
+            let _ = write!(mac, "{:02x}", byte);
+        }
+        Info {
+            ver: VERSION,
+            vid: VERSION_ID,
+            leds: Leds {
+                count: device.leds,
+                rgbw: false,
+                wv: false,
+                cct: false,
+                pwr: 0,
+                fps: stream::FPS,
+                maxpwr: 0,
+                maxseg: 1,
+                seglc: [1],
+                lc: 1,
+            },
+            str: false,
+            name: device.name,
+            // Realtime packets, with the `stream` feature of the firmware
+            udpport: crate::stream::wled::PORT,
+            live: false,
+            fxcount: NAMES.len(),
+            palcount: PALETTES.len(),
+            arch: "rp2040",
+            // The apps only talk to WLED
+            brand: "WLED",
+            product: NAME,
+            mac,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct StateInfo<'a> {
+    state: State,
+    info: Info<'a>,
+}
+
+#[derive(Serialize)]
+struct All<'a> {
+    state: State,
+    info: Info<'a>,
+    effects: &'static [&'static str],
+    palettes: &'static [&'static str],
+}
+
+#[derive(Serialize)]
+struct Success {
+    success: bool,
+}
+
+#[derive(Deserialize)]
+struct SegmentChange {
+    fx: Option<usize>,
+}
+
+/// `"seg"`, an array of segments or a single one
+struct Segments(Vec<SegmentChange, 4>);
+
+impl<'de> Deserialize<'de> for Segments {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = Segments;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a segment or an array of segments")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<Segments, A::Error> {
+                Vec::deserialize(SeqAccessDeserializer::new(seq)).map(Segments)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Segments, A::Error> {
+                let segment = SegmentChange::deserialize(MapAccessDeserializer::new(map))?;
+                let mut segments = Vec::new();
+                // Fits, the first one
+                let _ = segments.push(segment);
+                Ok(Segments(segments))
+            }
+        }
+
+        // serde_json_core has no `deserialize_any`, this one still looks at
+        // what comes and visits an array or an object
+        deserializer.deserialize_ignored_any(Visitor)
+    }
+}
+
+/// WLED's `"on":"t"`
+#[derive(Deserialize)]
+enum Toggle {
+    #[serde(rename = "t")]
+    Toggle,
+}
+
+/// What `"on"` asks for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Power {
+    Set(bool),
+    Toggle,
+}
+
+#[derive(Deserialize)]
+struct StateChange<On> {
+    on: Option<On>,
+    bri: Option<u8>,
+    seg: Option<Segments>,
+    /// Answer with the new state
+    #[serde(default)]
+    v: bool,
+}
+
+impl<On> StateChange<On> {
+    fn map_on(self, f: impl FnOnce(On) -> Power) -> StateChange<Power> {
+        StateChange {
+            on: self.on.map(f),
+            bri: self.bri,
+            seg: self.seg,
+            v: self.v,
+        }
+    }
+}
+
+/// The change asked for by `body`, tried with a bool `"on"` then with
+/// `"t"`, serde_json_core can't tell them apart in one go
+fn parse_change(body: &[u8]) -> Option<StateChange<Power>> {
+    match super::parse::<StateChange<bool>>(body) {
+        Some(change) => Some(change.map_on(Power::Set)),
+        None => super::parse::<StateChange<Toggle>>(body)
+            .map(|change| change.map_on(|Toggle::Toggle| Power::Toggle)),
+    }
+}
+
+/// Answer `request` to a `/json` path given the current `status`, with
+/// the commands that carry it out
+pub fn handle(request: &Request, status: Status, device: &Device) -> (Response, Commands) {
+    let none = Commands::new();
+    let state = |status: Status| Response::json(200, &State::new(status, device));
+
+    match (request.path, request.method) {
+        ("/json/state", Method::Get) => (state(status), none),
+        ("/json/info", Method::Get) => (Response::json(200, &Info::new(device)), none),
+        ("/json/effects", Method::Get) => (Response::json(200, &NAMES), none),
+        ("/json/palettes", Method::Get) => (Response::json(200, &PALETTES), none),
+        ("/json/si", Method::Get) => {
+            let body = StateInfo {
+                state: State::new(status, device),
+                info: Info::new(device),
+            };
+            (Response::json(200, &body), none)
+        }
+        ("/json", Method::Get) => {
+            let body = All {
+                state: State::new(status, device),
+                info: Info::new(device),
+                effects: &NAMES,
+                palettes: &PALETTES,
+            };
+            (Response::json(200, &body), none)
+        }
+        ("/json/state" | "/json/si" | "/json", Method::Post) => {
+            let Some(change) = parse_change(request.body) else {
+                return (Response::error(400, "Invalid state"), none);
+            };
+            match apply(&change, status) {
+                Some((status, commands)) if change.v => (state(status), commands),
+                Some((_, commands)) => (Response::json(200, &Success { success: true }), commands),
+                None => (Response::error(400, "Unknown effect"), none),
+            }
+        }
+        (
+            "/json/state" | "/json/info" | "/json/effects" | "/json/palettes" | "/json/si"
+            | "/json",
+            _,
+        ) => (Response::error(405, "Method not allowed"), none),
+        _ => (Response::error(404, "Not found"), none),
+    }
+}
+
+/// The status after `change` and the commands to get there. Showing a
+/// world switches the power on, so it goes first and the power last.
+fn apply(change: &StateChange<Power>, mut status: Status) -> Option<(Status, Commands)> {
+    let mut commands = Commands::new();
+
+    let effect = change
+        .seg
+        .iter()
+        .flat_map(|segments| &segments.0)
+        .find_map(|segment| segment.fx);
+    if let Some(world) = effect {
+        if world >= NAMES.len() {
+            return None;
+        }
+        status.world = world;
+        status.on = true;
+        let _ = commands.push(Command::World(world));
+    }
+    let power = match change.bri {
+        // WLED's off, the brightness stays for the next on
+        Some(0) => Some(Power::Set(false)),
+        Some(brightness) => {
+            status.brightness = brightness;
+            let _ = commands.push(Command::Brightness(brightness));
+            change.on
+        }
+        None => change.on,
+    };
+    match power {
+        Some(Power::Set(on)) => {
+            status.on = on;
+            let _ = commands.push(Command::Power(on));
+        }
+        Some(Power::Toggle) => {
+            status.on = !status.on;
+            let _ = commands.push(Command::SwitchPower);
+        }
+        None => {}
+    }
+    Some((status, commands))
+}
@@ -0,0 +1,79 @@
+//! Proximity offset calibration. Light the IR LED bounces off the diffuser
+//! adds a baseline to every `PDATA` reading, which differs from one lantern
+//! to the next. The sensor subtracts the offset programmed in `POFFSET_UR`
+//! and `POFFSET_DL`, so the search below looks for the smallest offset that
+//! brings the idle baseline down to a target.
+
+/// Largest offset magnitude the `POFFSET` registers hold
+pub const MAX_OFFSET: u8 = 127;
+
+/// Value of the `POFFSET` registers, in sign-magnitude. Positive offsets
+/// remove counts from `PDATA`, negative ones add counts.
+pub fn poffset(offset: i8) -> u8 {
+    let magnitude = offset.unsigned_abs().min(MAX_OFFSET);
+    match offset < 0 {
+        true => 0x80 | magnitude,
+        false => magnitude,
+    }
+}
+
+/// Idle baseline of a batch of readings, their median so that a stray
+/// reflection does not skew it. Sorts `samples`.
+pub fn baseline(samples: &mut [u8]) -> u8 {
+    if samples.is_empty() {
+        return 0;
+    }
+    samples.sort_unstable();
+    samples[samples.len() / 2]
+}
+
+/// Bisection over the offsets from 0 to `MAX_OFFSET`. The higher the
+/// offset the lower the baseline, so a handful of measurements is enough.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+    target: u8,
+    // Smallest offset that may still be the answer
+    low: u8,
+    // Smallest offset known to bring the baseline to the target,
+    // `MAX_OFFSET + 1` until one does
+    high: u8,
+}
+
+impl Calibration {
+    /// Search for a baseline at or below `target`
+    pub fn new(target: u8) -> Self {
+        Calibration {
+            target,
+            low: 0,
+            high: MAX_OFFSET + 1,
+        }
+    }
+
+    /// Offset to program before measuring the next baseline, `None` once
+    /// the search is over
+    pub fn offset(&self) -> Option<i8> {
+        (self.low < self.high).then(|| self.middle() as i8)
+    }
+
+    /// Baseline measured with the offset last returned by `offset`
+    pub fn measured(&mut self, baseline: u8) {
+        if self.low >= self.high {
+            return;
+        }
+        let middle = self.middle();
+        match baseline <= self.target {
+            true => self.high = middle,
+            false => self.low = middle + 1,
+        }
+    }
+
+    /// Offset found, `MAX_OFFSET` when even that leaves the baseline above
+    /// the target
+    pub fn result(&self) -> i8 {
+        self.low.min(MAX_OFFSET) as i8
+    }
+
+    fn middle(&self) -> u8 {
+        (self.low + self.high) / 2
+    }
+}
@@ -1,4 +1,4 @@
-use crate::perlin::rand_float;
+use crate::random::RandomSource;
 use core::cmp::max;
 use core::cmp::Ordering;
 use heapless::Vec;
@@ -10,6 +10,7 @@ pub struct Color {
     pos: f32,
     rgb: RGB8,
 }
+#[cfg(feature = "defmt")]
 impl defmt::Format for Color {
     fn format(&self, fmt: defmt::Formatter<'_>) {
         defmt::write!(
@@ -92,7 +93,7 @@ impl<const COLORS: usize> ColorGradient<COLORS> {
     pub fn add_color(&mut self, color: Color) {
         match self.colors.push(color) {
             Ok(()) => (),
-            Err(_) => defmt::panic!("Gradient capacity exceeded"),
+            Err(_) => panic!("Gradient capacity exceeded"),
         }
 
         self.colors
@@ -116,24 +117,24 @@ impl<const COLORS: usize> ColorGradient<COLORS> {
                 ColorGradient::<COLORS>::lin_interp_colors(c1, c2, value)
             }
             Err(_) => {
-                defmt::panic!("Error while during bin search. Value: {}", value);
+                panic!("Error while during bin search. Value: {}", value);
             }
         }
     }
 
-    pub fn get_noised(&self, value: f32, min: f32, max: f32) -> RGB8 {
+    pub fn get_noised(&self, value: f32, min: f32, max: f32, rng: &mut impl RandomSource) -> RGB8 {
         match self.search_closest(value) {
             Ok(left) => {
                 let c1 = &self.colors[left];
                 let c2 = &self.colors[left + 1];
 
-                let value = value + rand_float(min, max);
+                let value = value + rng.rand_float(min, max);
                 let value = value.clamp(0.0, 1.0);
 
                 ColorGradient::<COLORS>::lin_interp_colors(c1, c2, value)
             }
             Err(_) => {
-                defmt::panic!("Error while during bin search");
+                panic!("Error while during bin search");
             }
         }
     }
@@ -154,7 +155,7 @@ impl<const COLORS: usize> ColorGradient<COLORS> {
                 return Ok(i - 1);
             }
         }
-        defmt::error!("Error search: value={}", value);
+        error!("Error search: value={}", value);
         Err(BinSearchError::InvalidSearch)
     }
 
@@ -172,6 +173,11 @@ impl<const COLORS: usize> ColorGradient<COLORS> {
             })
     }
 
+    /// Sum of the changes to the value of the colors
+    pub fn diff(&self) -> i8 {
+        self.diff
+    }
+
     pub fn colors(&self) -> &[Color] {
         self.colors.as_slice()
     }
@@ -0,0 +1,393 @@
+//! Text console for debugging and scripting over a serial line. Lines such
+//! as `world fire` or `up` turn into the same `Command`s the gestures
+//! send, only the transport is up to the board.
+
+use crate::api::Lantern;
+use crate::gesture::{Command, Direction, GestureConfig};
+use crate::proximity::{LedBoost, LedDrive, ProximityConfig, ProximityGain, PulseLength, WaitTime};
+use crate::world::NAMES;
+use core::fmt::{self, Write};
+use heapless::String;
+
+pub const PROMPT: &str = "> ";
+
+const HELP: &str = "\
+world <name>|next|prev  show a world
+up, down                change the parameter of the world
+brightness <0-255>      dim the LEDs
+power [on|off]          toggle or set the power
+calibrate               calibrate the proximity offset
+gesture [<field> <n>]   show or tune the gesture thresholds
+proximity [<field> <n>] show or tune the proximity readings
+status                  world, power and brightness
+sensor raw              latest proximity reading
+";
+
+/// The lantern as seen from the console
+pub trait Console: Lantern {
+    /// Latest `PDATA` reading, `None` while the sensor is missing
+    fn proximity(&self) -> Option<u8>;
+    /// Gesture thresholds last asked for
+    fn gesture_config(&self) -> GestureConfig;
+    /// Queue `config` for the gesture recognizer, a gesture in progress is
+    /// dropped
+    async fn set_gesture_config(&self, config: GestureConfig);
+    /// Proximity readings last asked for
+    fn proximity_config(&self) -> ProximityConfig;
+    /// Queue `config` for the sensor, valid already
+    async fn set_proximity_config(&self, config: ProximityConfig);
+}
+
+/// Collects the bytes of a serial line until a line ending
+#[derive(Debug, Default)]
+pub struct LineBuffer<const N: usize> {
+    line: String<N>,
+    // Line just ended, drop what is left of the line ending
+    ended: bool,
+}
+
+impl<const N: usize> LineBuffer<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The line once `byte` ends it. Backspace and delete remove the last
+    /// character, bytes past `N` or out of ASCII are dropped.
+    pub fn push(&mut self, byte: u8) -> Option<&str> {
+        if self.ended {
+            self.line.clear();
+            self.ended = false;
+            // Second half of a CR LF
+            if byte == b'\n' {
+                return None;
+            }
+        }
+
+        match byte {
+            b'\r' | b'\n' => {
+                self.ended = true;
+                return Some(self.line.trim());
+            }
+            0x08 | 0x7F => {
+                self.line.pop();
+            }
+            byte if byte.is_ascii() && !byte.is_ascii_control() => {
+                let _ = self.line.push(byte as char);
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Request {
+    Command(Command),
+    Status,
+    SensorRaw,
+    /// Show the gesture thresholds, or change one
+    Gesture(Option<GestureSetting>),
+    /// Show the proximity config, or change one field
+    Proximity(Option<ProximitySetting>),
+    Help,
+    /// Blank line
+    Empty,
+}
+
+/// One field of `GestureConfig` with its new value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GestureSetting {
+    Floor(u8),
+    EntryChecks(u32),
+    SwingWindow(u32),
+    Near(u8),
+    PowerChecks(u32),
+    CalibrateChecks(u32),
+    LevelChecks(u32),
+    LevelThreshold(i16),
+}
+
+const GESTURE_USAGE: &str = "gesture [floor|entry_checks|swing_window|near|power_checks|calibrate_checks|level_checks|level_threshold <n>]";
+
+impl GestureSetting {
+    fn parse(field: &str, value: &str) -> Option<Self> {
+        let setting = match field {
+            "floor" => GestureSetting::Floor(value.parse().ok()?),
+            "entry_checks" => GestureSetting::EntryChecks(value.parse().ok()?),
+            "swing_window" => GestureSetting::SwingWindow(value.parse().ok()?),
+            "near" => GestureSetting::Near(value.parse().ok()?),
+            "power_checks" => GestureSetting::PowerChecks(value.parse().ok()?),
+            "calibrate_checks" => GestureSetting::CalibrateChecks(value.parse().ok()?),
+            "level_checks" => GestureSetting::LevelChecks(value.parse().ok()?),
+            "level_threshold" => GestureSetting::LevelThreshold(value.parse().ok()?),
+            _ => return None,
+        };
+        Some(setting)
+    }
+
+    pub fn apply(&self, config: &mut GestureConfig) {
+        match *self {
+            GestureSetting::Floor(floor) => config.floor = floor,
+            GestureSetting::EntryChecks(checks) => config.entry_checks = checks,
+            GestureSetting::SwingWindow(window) => config.swing_window = window,
+            GestureSetting::Near(near) => config.near = near,
+            GestureSetting::PowerChecks(checks) => config.power_checks = checks,
+            GestureSetting::CalibrateChecks(checks) => config.calibrate_checks = checks,
+            GestureSetting::LevelChecks(checks) => config.level_checks = checks,
+            GestureSetting::LevelThreshold(threshold) => config.level_threshold = threshold,
+        }
+    }
+}
+
+/// One field of `ProximityConfig` with its new value, in the range the
+/// datasheet allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProximitySetting {
+    Gain(ProximityGain),
+    LedDrive(LedDrive),
+    LedBoost(LedBoost),
+    PulseLength(PulseLength),
+    Pulses(u8),
+    Wait(Option<WaitTime>),
+}
+
+const PROXIMITY_USAGE: &str = "proximity [gain 1|2|4|8, led_drive 100|50|25|12.5, led_boost 1|1.5|2|3, pulse_length 4|8|16|32, pulses 1-64, wait off|1-256, wait_long 1-256]";
+
+// Values of the fields as the console reads and writes them
+const GAINS: [(&str, ProximityGain); 4] = [
+    ("1", ProximityGain::X1),
+    ("2", ProximityGain::X2),
+    ("4", ProximityGain::X4),
+    ("8", ProximityGain::X8),
+];
+const DRIVES: [(&str, LedDrive); 4] = [
+    ("100", LedDrive::Ma100),
+    ("50", LedDrive::Ma50),
+    ("25", LedDrive::Ma25),
+    ("12.5", LedDrive::Ma12_5),
+];
+const BOOSTS: [(&str, LedBoost); 4] = [
+    ("1", LedBoost::X1),
+    ("1.5", LedBoost::X1_5),
+    ("2", LedBoost::X2),
+    ("3", LedBoost::X3),
+];
+const LENGTHS: [(&str, PulseLength); 4] = [
+    ("4", PulseLength::Us4),
+    ("8", PulseLength::Us8),
+    ("16", PulseLength::Us16),
+    ("32", PulseLength::Us32),
+];
+
+fn lookup<T: Copy>(values: &[(&str, T)], value: &str) -> Option<T> {
+    values
+        .iter()
+        .find(|(name, _)| *name == value)
+        .map(|(_, value)| *value)
+}
+
+fn name<T: PartialEq>(values: &[(&'static str, T)], value: T) -> &'static str {
+    values
+        .iter()
+        .find(|(_, known)| *known == value)
+        .map(|(name, _)| *name)
+        .unwrap_or_default()
+}
+
+impl ProximitySetting {
+    fn parse(field: &str, value: &str) -> Option<Self> {
+        let cycles = |long| {
+            let cycles = value
+                .parse()
+                .ok()
+                .filter(|cycles| (1..=256).contains(cycles))?;
+            Some(ProximitySetting::Wait(Some(WaitTime { cycles, long })))
+        };
+        match field {
+            "gain" => lookup(&GAINS, value).map(ProximitySetting::Gain),
+            "led_drive" => lookup(&DRIVES, value).map(ProximitySetting::LedDrive),
+            "led_boost" => lookup(&BOOSTS, value).map(ProximitySetting::LedBoost),
+            "pulse_length" => lookup(&LENGTHS, value).map(ProximitySetting::PulseLength),
+            "pulses" => value
+                .parse()
+                .ok()
+                .filter(|pulses| (1..=64).contains(pulses))
+                .map(ProximitySetting::Pulses),
+            "wait" if value == "off" => Some(ProximitySetting::Wait(None)),
+            "wait" => cycles(false),
+            "wait_long" => cycles(true),
+            _ => None,
+        }
+    }
+
+    pub fn apply(&self, config: &mut ProximityConfig) {
+        match *self {
+            ProximitySetting::Gain(gain) => config.gain = gain,
+            ProximitySetting::LedDrive(drive) => config.led_drive = drive,
+            ProximitySetting::LedBoost(boost) => config.led_boost = boost,
+            ProximitySetting::PulseLength(length) => config.pulse_length = length,
+            ProximitySetting::Pulses(pulses) => config.pulses = pulses,
+            ProximitySetting::Wait(wait) => config.wait = wait,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError<'a> {
+    Unknown(&'a str),
+    UnknownWorld(&'a str),
+    /// Known command, wrong arguments. Holds its usage.
+    Usage(&'static str),
+}
+
+impl fmt::Display for ParseError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Unknown(word) => write!(f, "unknown command {word}, try help"),
+            ParseError::UnknownWorld(name) => write!(f, "unknown world {name}"),
+            ParseError::Usage(usage) => write!(f, "usage: {usage}"),
+        }
+    }
+}
+
+pub fn parse(line: &str) -> Result<Request, ParseError<'_>> {
+    let mut words = line.split_whitespace();
+    let Some(word) = words.next() else {
+        return Ok(Request::Empty);
+    };
+    let argument = words.next();
+    // Only the settings take a value after their field
+    let value = words.next();
+    let extra =
+        words.next().is_some() || (value.is_some() && !matches!(word, "gesture" | "proximity"));
+
+    let request = match (word, argument) {
+        ("world", Some("next")) => Request::Command(Command::Swipe(Direction::Right)),
+        ("world", Some("prev")) => Request::Command(Command::Swipe(Direction::Left)),
+        ("world", Some(name)) => match NAMES.iter().position(|world| *world == name) {
+            Some(index) => Request::Command(Command::World(index)),
+            None => return Err(ParseError::UnknownWorld(name)),
+        },
+        ("world", None) => return Err(ParseError::Usage("world <name>|next|prev")),
+        ("up", None) => Request::Command(Command::Level(Direction::Up)),
+        ("down", None) => Request::Command(Command::Level(Direction::Down)),
+        ("brightness", Some(level)) => match level.parse() {
+            Ok(level) => Request::Command(Command::Brightness(level)),
+            Err(_) => return Err(ParseError::Usage("brightness <0-255>")),
+        },
+        ("brightness", None) => return Err(ParseError::Usage("brightness <0-255>")),
+        ("power", None) => Request::Command(Command::SwitchPower),
+        ("power", Some("on")) => Request::Command(Command::Power(true)),
+        ("power", Some("off")) => Request::Command(Command::Power(false)),
+        ("power", Some(_)) => return Err(ParseError::Usage("power [on|off]")),
+        ("calibrate", None) => Request::Command(Command::Calibrate),
+        ("gesture", None) => Request::Gesture(None),
+        ("gesture", Some(field)) => {
+            match value.and_then(|value| GestureSetting::parse(field, value)) {
+                Some(setting) => Request::Gesture(Some(setting)),
+                None => return Err(ParseError::Usage(GESTURE_USAGE)),
+            }
+        }
+        ("proximity", None) => Request::Proximity(None),
+        ("proximity", Some(field)) => {
+            match value.and_then(|value| ProximitySetting::parse(field, value)) {
+                Some(setting) => Request::Proximity(Some(setting)),
+                None => return Err(ParseError::Usage(PROXIMITY_USAGE)),
+            }
+        }
+        ("status", None) => Request::Status,
+        ("sensor", Some("raw")) => Request::SensorRaw,
+        ("sensor", _) => return Err(ParseError::Usage("sensor raw")),
+        ("help", None) => Request::Help,
+        // Arguments to a command that takes none
+        ("up" | "down" | "calibrate" | "status" | "help", Some(_)) => {
+            return Err(ParseError::Unknown(line))
+        }
+        (word, _) => return Err(ParseError::Unknown(word)),
+    };
+
+    match extra {
+        true => Err(ParseError::Unknown(line)),
+        false => Ok(request),
+    }
+}
+
+/// Run `line` against the lantern and write the answer to `out`, lines end
+/// with CR LF
+pub async fn dispatch(line: &str, console: &impl Console, out: &mut impl Write) -> fmt::Result {
+    let request = match parse(line) {
+        Ok(request) => request,
+        Err(err) => return write!(out, "{err}\r\n"),
+    };
+
+    match request {
+        Request::Command(command) => {
+            console.send(command).await;
+            out.write_str("ok\r\n")
+        }
+        Request::Status => {
+            let status = console.status();
+            let world = NAMES.get(status.world).copied().unwrap_or_default();
+            let power = if status.on { "on" } else { "off" };
+            write!(
+                out,
+                "world {world}, {power}, brightness {}\r\n",
+                status.brightness
+            )
+        }
+        Request::SensorRaw => match console.proximity() {
+            Some(pdata) => write!(out, "pdata {pdata}\r\n"),
+            None => out.write_str("sensor missing\r\n"),
+        },
+        Request::Gesture(Some(setting)) => {
+            let mut config = console.gesture_config();
+            setting.apply(&mut config);
+            console.set_gesture_config(config).await;
+            out.write_str("ok\r\n")
+        }
+        Request::Gesture(None) => {
+            let config = console.gesture_config();
+            write!(
+                out,
+                "floor {}, entry_checks {}, swing_window {}, near {}, power_checks {}, \
+                 calibrate_checks {}, level_checks {}, level_threshold {}\r\n",
+                config.floor,
+                config.entry_checks,
+                config.swing_window,
+                config.near,
+                config.power_checks,
+                config.calibrate_checks,
+                config.level_checks,
+                config.level_threshold
+            )
+        }
+        Request::Proximity(Some(setting)) => {
+            let mut config = console.proximity_config();
+            setting.apply(&mut config);
+            console.set_proximity_config(config).await;
+            out.write_str("ok\r\n")
+        }
+        Request::Proximity(None) => {
+            let config = console.proximity_config();
+            write!(
+                out,
+                "gain {}, led_drive {}, led_boost {}, pulse_length {}, pulses {}, ",
+                name(&GAINS, config.gain),
+                name(&DRIVES, config.led_drive),
+                name(&BOOSTS, config.led_boost),
+                name(&LENGTHS, config.pulse_length),
+                config.pulses
+            )?;
+            match config.wait {
+                None => out.write_str("wait off\r\n"),
+                Some(WaitTime {
+                    cycles,
+                    long: false,
+                }) => write!(out, "wait {cycles}\r\n"),
+                Some(WaitTime { cycles, long: true }) => write!(out, "wait_long {cycles}\r\n"),
+            }
+        }
+        Request::Help => HELP.lines().try_for_each(|line| write!(out, "{line}\r\n")),
+        Request::Empty => Ok(()),
+    }
+}
@@ -1,130 +1,43 @@
-#![allow(dead_code)]
-use core::option::Option;
-use embassy_rp::i2c::Instance;
-use embassy_rp::i2c::{self, Error, Mode};
-use embedded_hal_async::i2c::I2c;
-
-pub const DEV_ADDR: u8 = 0x39;
-
-pub struct Register;
-impl Register {
-    const ENABLE: u8 = 0x80;
-    const ATIME: u8 = 0x81;
-    const WTIME: u8 = 0x83;
-    const AILTL: u8 = 0x84;
-    const AIHTL: u8 = 0x86;
-    const PILT: u8 = 0x89;
-    const PIHT: u8 = 0x8B;
-    const CONFIG1: u8 = 0x8D;
-    const CONTROL: u8 = 0x8F;
-    const CONFIG2: u8 = 0x90;
-    const ID: u8 = 0x92;
-    const STATUS: u8 = 0x93;
-    const CDATAL: u8 = 0x94;
-    const RDATAL: u8 = 0x96;
-    const GDATAL: u8 = 0x98;
-    const BDATAL: u8 = 0x9A;
-    const PDATA: u8 = 0x9C;
-    const POFFSET_UR: u8 = 0x9D;
-    const POFFSET_DL: u8 = 0x9E;
-    const GPENTH: u8 = 0xA0;
-    const GPEXTH: u8 = 0xA1;
-    const GCONFIG1: u8 = 0xA2;
-    const GOFFSET_U: u8 = 0xA4;
-    const GOFFSET_D: u8 = 0xA5;
-    const GOFFSET_L: u8 = 0xA6;
-    const GOFFSET_R: u8 = 0xA7;
-    const GCONFIG4: u8 = 0xAB;
-    const GFLVL: u8 = 0xAE;
-    const GSTATUS: u8 = 0xAF;
-    const IFORCE: u8 = 0xE4;
-    const PICLEAR: u8 = 0xE5;
-    const CICLEAR: u8 = 0xE6;
-    const AICLEAR: u8 = 0xE7;
-    const GFIFO_U: u8 = 0xFC;
-}
-
-pub struct Enable(u8);
-impl Enable {
-    const ALL: u8 = 0b1111_1111;
-    const PON: u8 = 0b0000_0001;
-    const AEN: u8 = 0b0000_0010;
-    const PEN: u8 = 0b0000_0100;
-    const WEN: u8 = 0b0000_1000;
-    const AIEN: u8 = 0b0001_0000;
-    const PIEN: u8 = 0b0010_0000;
-    const GEN: u8 = 0b0100_0000;
-}
-
-pub struct Status(u8);
-impl Status {
-    pub const AVALID: u8 = 0b0000_0001;
-    pub const PVALID: u8 = 0b0000_0010;
-}
-
-pub struct Apds9960<'d, T, M>
-where
-    T: Instance,
-    M: Mode,
-{
-    i2c: i2c::I2c<'d, T, M>,
-    sm: StateMashine,
+/// Tuning of the gesture recognizer, in `PDATA` counts and samples
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GestureConfig {
+    /// Readings at or below mean no hand above the sensor
+    pub floor: u8,
+    /// Readings above the floor before a gesture starts
+    pub entry_checks: u32,
+    /// Longest gesture, counted from its start, still taken as a swing
+    pub swing_window: u32,
+    /// Readings at or above mean a hand close to the sensor
+    pub near: u8,
+    /// Close readings past which taking the hand away switches the power
+    pub power_checks: u32,
+    /// Close readings past which taking the hand away calibrates the
+    /// proximity offset instead, more than `power_checks`
+    pub calibrate_checks: u32,
+    /// Readings between two level steps
+    pub level_checks: u32,
+    /// Change of distance beyond which the level steps up or down
+    pub level_threshold: i16,
 }
 
-impl<'d, T: Instance> Apds9960<'d, T, i2c::Async> {
-    pub fn new(i2c: i2c::I2c<'d, T, i2c::Async>) -> Self {
-        let sm = StateMashine::default();
-        Apds9960 { i2c, sm }
-    }
-
-    pub async fn enable(&mut self) -> Result<(), Error> {
-        self.i2c
-            .write(DEV_ADDR, &[Register::ENABLE, Enable::PON | Enable::PEN])
-            .await?;
-        Ok(())
-    }
-
-    pub async fn powerup(&mut self) -> Result<(), Error> {
-        self.i2c
-            .write(DEV_ADDR, &[Register::CONTROL, 0b0000_0000])
-            .await?; //LED DRIVE
-
-        self.i2c
-            .write(DEV_ADDR, &[Register::CONFIG2, 0b0011_0000])
-            .await?; // LED BOOST
-        Ok(())
-    }
-
-    pub async fn read(&mut self) -> Result<u8, Error> {
-        let mut is_prox = [0u8];
-        self.i2c
-            .write_read(DEV_ADDR, &[Register::STATUS], &mut is_prox)
-            .await?;
-
-        let mut prox = [0u8];
-        if is_prox[0] & Status::PVALID != 0 {
-            self.i2c
-                .write_read(DEV_ADDR, &[Register::PDATA], &mut prox)
-                .await?;
-
-            return Ok(prox[0]);
-        }
-        Err(Error::Abort(i2c::AbortReason::Other(42)))
-    }
-
-    pub async fn gesture(&mut self) {
-        if let Ok(dist) = self.read().await {
-            self.sm.next(dist);
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            floor: 3,
+            entry_checks: 8,
+            swing_window: 30,
+            near: 200,
+            power_checks: 20,
+            calibrate_checks: 300,
+            level_checks: 6,
+            level_threshold: 1,
         }
     }
-
-    pub fn command(&mut self) -> Option<Command> {
-        self.sm.command()
-    }
 }
 
 #[derive(Default)]
-struct StateMashine {
+pub struct StateMashine {
+    config: GestureConfig,
     state: State,
     succ_checks: u32,
     power_checks: u32,
@@ -135,9 +48,31 @@ struct StateMashine {
 }
 
 impl StateMashine {
-    const UP_DOWN_THRESHOLD: i16 = 1;
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn config(&self) -> GestureConfig {
+        self.config
+    }
+
+    /// Takes effect from the next reading, a gesture in progress is dropped
+    pub fn set_config(&mut self, config: GestureConfig) {
+        self.config = config;
+        self.command = None;
+        self.state = State::Check;
+        self.reset();
+    }
 
-    fn next(&mut self, dist: u8) {
+    /// Waiting for a hand, the last reading was at or below the floor
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, State::Check) && self.succ_checks == 0
+    }
+
+    pub fn next(&mut self, dist: u8) {
         self.state = self.process(dist);
     }
 
@@ -150,10 +85,11 @@ impl StateMashine {
     }
 
     fn process(&mut self, dist: u8) -> State {
+        let config = self.config;
         match self.state {
             State::Check => match dist {
                 // dist > 0
-                dist if dist > 3 => match self.succ_checks > 7 {
+                dist if dist > config.floor => match self.succ_checks >= config.entry_checks {
                     true => {
                         self.succ_checks += 1;
                         self.recorded = self.succ_checks;
@@ -171,9 +107,9 @@ impl StateMashine {
                 }
             },
 
-            State::Swing => match self.recorded <= 30 {
+            State::Swing => match self.recorded <= config.swing_window {
                 // Gesture was fast...
-                true => match dist <= 3 {
+                true => match dist <= config.floor {
                     // ... and now finished
                     true => {
                         // Swing
@@ -188,7 +124,7 @@ impl StateMashine {
                     }
                 },
                 // Gesture is slow, not just swing
-                false => match dist <= 3 {
+                false => match dist <= config.floor {
                     true => {
                         // Swing
                         self.command = Some(Command::Swing);
@@ -204,43 +140,38 @@ impl StateMashine {
             },
 
             State::Record => match dist {
-                // Hand close to sensor...
-                dist if dist >= 200 => match self.power_checks {
-                    // ... for a short time
-                    checks if checks < 20 => {
-                        self.power_checks += 1;
-                        State::Record
-                    }
-                    // ... switch the power
-                    20 => {
-                        // Power Switch
+                // Hand close to sensor, how long tells once it is gone
+                dist if dist >= config.near => {
+                    self.power_checks = self.power_checks.saturating_add(1);
+                    State::Record
+                }
+                // Gesture is over
+                dist if dist <= config.floor => {
+                    // A long hold calibrates against the empty lantern, a
+                    // shorter one switches the power
+                    if self.power_checks > config.calibrate_checks {
+                        self.command = Some(Command::Calibrate);
+                    } else if self.power_checks > config.power_checks {
                         self.command = Some(Command::SwitchPower);
-                        self.power_checks += 1;
-                        State::Record
                     }
-                    // ... for a long time
-                    _checks => State::Record,
-                },
-                // Gesture is over
-                dist if dist <= 3 => {
                     self.reset();
                     State::Check
                 }
 
                 // Hand at middle distance from sensor
-                dist => match self.updown_checks > 5 {
+                dist => match self.updown_checks >= config.level_checks {
                     true => {
                         // UP DOWN
                         match (self.init_dist as i16) - (dist as i16) {
                             //self.init_dist < dist {
-                            d if d < -StateMashine::UP_DOWN_THRESHOLD => {
+                            d if d < -config.level_threshold => {
                                 //DOWN
                                 self.command = Some(Command::Level(Direction::Down));
                                 self.updown_checks = 0;
                                 self.init_dist = dist;
                                 State::Record
                             }
-                            d if d > StateMashine::UP_DOWN_THRESHOLD => {
+                            d if d > config.level_threshold => {
                                 // UP
                                 self.command = Some(Command::Level(Direction::Up));
                                 self.updown_checks = 0;
@@ -268,34 +199,61 @@ impl StateMashine {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Command {
     Swing,
     SwitchPower,
+    /// Switch the power on or off, whatever it is now, from a remote
+    Power(bool),
     Level(Direction),
+    /// Directional gesture from the sensor's gesture engine
+    Swipe(Direction),
+    /// Measure the idle baseline and program the proximity offset
+    Calibrate,
+    /// Show the world at this index in `world::NAMES`, from a remote
+    World(usize),
+    /// Scale every LED, from 0 (off) to 255 (full), from a remote
+    Brightness(u8),
 }
 
+#[cfg(feature = "defmt")]
 impl defmt::Format for Command {
     fn format(&self, fmt: defmt::Formatter) {
         match self {
             Command::Swing => defmt::write!(fmt, "Swing"),
             Command::SwitchPower => defmt::write!(fmt, "SwitchPower"),
+            Command::Power(on) => defmt::write!(fmt, "Power({})", on),
             Command::Level(direction) => defmt::write!(fmt, "Level({:?})", direction),
+            Command::Swipe(direction) => defmt::write!(fmt, "Swipe({:?})", direction),
+            Command::Calibrate => defmt::write!(fmt, "Calibrate"),
+            Command::World(index) => defmt::write!(fmt, "World({})", index),
+            Command::Brightness(level) => defmt::write!(fmt, "Brightness({})", level),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Up,
     Down,
+    Left,
+    Right,
+    /// Hand held close above the sensor
+    Near,
+    /// Hand withdrawn after `Near`
+    Far,
 }
 
+#[cfg(feature = "defmt")]
 impl defmt::Format for Direction {
     fn format(&self, fmt: defmt::Formatter) {
         match self {
             Direction::Up => defmt::write!(fmt, "Up"),
             Direction::Down => defmt::write!(fmt, "Down"),
+            Direction::Left => defmt::write!(fmt, "Left"),
+            Direction::Right => defmt::write!(fmt, "Right"),
+            Direction::Near => defmt::write!(fmt, "Near"),
+            Direction::Far => defmt::write!(fmt, "Far"),
         }
     }
 }
@@ -0,0 +1,268 @@
+use core::sync::atomic::{AtomicU8, Ordering};
+use micromath::F32Ext;
+use smart_leds::RGB8;
+
+// Brightness of the matrices built without one
+static FULL: AtomicU8 = AtomicU8::new(u8::MAX);
+
+pub trait PioWrite<const N: usize> {
+    async fn write(&mut self, colors: &[RGB8; N]);
+}
+
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
+}
+
+pub trait WritableMatrix {
+    fn write(&mut self, x: usize, y: usize, color: RGB8);
+    fn write_straight(&mut self, index: usize, color: RGB8);
+    fn clear(&mut self);
+    fn bg(&mut self, bg: RGB8);
+    fn read(&self, x: usize, y: usize) -> RGB8;
+    async fn flush(&mut self);
+}
+pub struct LedMatrix<'ws, Ws: PioWrite<N>, const L: usize, const N: usize> {
+    data: [RGB8; N],
+    ws: &'ws mut Ws,
+    brightness: &'ws AtomicU8,
+    budget: Option<PowerBudget>,
+    lut: Option<Lut>,
+}
+
+impl<'ws, Ws: PioWrite<N>, const L: usize, const N: usize> LedMatrix<'ws, Ws, L, N> {
+    pub fn new(ws: &'ws mut Ws) -> Self {
+        Self::with_brightness(ws, &FULL)
+    }
+
+    /// Every LED is scaled by `brightness` on flush, which may change
+    /// while the worlds hold the matrix
+    pub fn with_brightness(ws: &'ws mut Ws, brightness: &'ws AtomicU8) -> Self {
+        Self {
+            data: [RGB8::default(); N],
+            ws,
+            brightness,
+            budget: None,
+            lut: None,
+        }
+    }
+
+    /// Correct every LED on flush, after the brightness
+    pub fn set_correction(&mut self, correction: &Correction) {
+        self.lut = Some(Lut::new(correction));
+    }
+
+    /// Dim the frames that would draw more than `budget` allows, on top
+    /// of the brightness
+    pub fn set_power_budget(&mut self, budget: PowerBudget) {
+        self.budget = Some(budget);
+    }
+
+    fn index(&self, x: usize, y: usize) -> usize {
+        strip_index::<L>(x, y)
+    }
+}
+
+/// Position of the LED at `(x, y)` along the strip. Columns of `L` LEDs are
+/// wired in a serpentine, so every odd column runs backwards.
+pub fn strip_index<const L: usize>(x: usize, y: usize) -> usize {
+    match x % 2 {
+        0 => x * L + y,
+        _ => x * L + (L - y) - 1,
+    }
+}
+
+/// Scale `color` by `brightness`, the same way as `smart_leds::brightness`
+pub fn dim(color: RGB8, brightness: u8) -> RGB8 {
+    let scale = |c: u8| (c as u16 * (brightness as u16 + 1) / 256) as u8;
+    RGB8::new(scale(color.r), scale(color.g), scale(color.b))
+}
+
+/// Current drawn by the LEDs, estimated from the levels of their channels.
+/// A supply asked for more sags and browns the Pico out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerBudget {
+    /// Most the LEDs may draw together, in mA
+    pub limit: u32,
+    /// Draw of a red channel at full level, in mA
+    pub red: u32,
+    /// Draw of a green channel at full level, in mA
+    pub green: u32,
+    /// Draw of a blue channel at full level, in mA
+    pub blue: u32,
+    /// Draw of an LED with every channel off, in mA
+    pub idle: u32,
+}
+
+impl Default for PowerBudget {
+    /// WS2812B from the datasheet, within what a USB 3 port supplies
+    fn default() -> Self {
+        PowerBudget {
+            limit: 900,
+            red: 20,
+            green: 20,
+            blue: 20,
+            idle: 1,
+        }
+    }
+}
+
+impl PowerBudget {
+    /// Estimated draw of `colors`, in mA
+    pub fn current(&self, colors: &[RGB8]) -> u32 {
+        self.idle * colors.len() as u32 + self.channels(colors)
+    }
+
+    /// Highest brightness up to `brightness` that keeps `colors` within
+    /// the limit
+    pub fn cap(&self, colors: &[RGB8], brightness: u8) -> u8 {
+        let channels = self.channels(colors);
+        let available = self.limit.saturating_sub(self.idle * colors.len() as u32);
+        // Scaled the same way as `dim`
+        if channels * (brightness as u32 + 1) / 256 <= available {
+            return brightness;
+        }
+        (available * 256 / channels).saturating_sub(1) as u8
+    }
+
+    /// Draw of the channels alone, in mA
+    fn channels(&self, colors: &[RGB8]) -> u32 {
+        let (mut red, mut green, mut blue) = (0u32, 0u32, 0u32);
+        for color in colors {
+            red += color.r as u32;
+            green += color.g as u32;
+            blue += color.b as u32;
+        }
+        (red * self.red + green * self.green + blue * self.blue) / u8::MAX as u32
+    }
+}
+
+/// Balance of the LED types, as in FastLED
+pub const UNCORRECTED: RGB8 = RGB8::new(255, 255, 255);
+pub const TYPICAL_SMD5050: RGB8 = RGB8::new(255, 176, 240);
+pub const TYPICAL_PIXEL_STRING: RGB8 = RGB8::new(255, 224, 140);
+
+/// Color temperatures of the white, as in FastLED
+pub const CANDLE: RGB8 = RGB8::new(255, 147, 41);
+pub const TUNGSTEN_100W: RGB8 = RGB8::new(255, 214, 170);
+pub const HALOGEN: RGB8 = RGB8::new(255, 241, 224);
+pub const DIRECT_SUNLIGHT: RGB8 = RGB8::new(255, 255, 255);
+pub const OVERCAST_SKY: RGB8 = RGB8::new(201, 226, 255);
+
+/// Turns the levels the worlds write into the levels the LEDs get. The
+/// worlds think in steps the eye sees as even, the LEDs light up in
+/// proportion to their level.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Correction {
+    /// Levels are raised to this power, 1.0 keeps them
+    pub gamma: f32,
+    /// Every channel scaled by its level here, see `TYPICAL_SMD5050`
+    pub color: RGB8,
+    /// Scaled on top of `color`, see `CANDLE`
+    pub temperature: RGB8,
+}
+
+impl Default for Correction {
+    /// Levels as they are
+    fn default() -> Self {
+        Correction {
+            gamma: 1.0,
+            color: UNCORRECTED,
+            temperature: DIRECT_SUNLIGHT,
+        }
+    }
+}
+
+/// Level of every channel for every level written, built from a
+/// `Correction` once
+#[derive(Clone)]
+pub struct Lut([[u8; 256]; 3]);
+
+impl Lut {
+    pub fn new(correction: &Correction) -> Self {
+        let scales = [
+            (correction.color.r, correction.temperature.r),
+            (correction.color.g, correction.temperature.g),
+            (correction.color.b, correction.temperature.b),
+        ];
+
+        let mut table = [[0; 256]; 3];
+        for (levels, (color, temperature)) in table.iter_mut().zip(scales) {
+            let scale = color as f32 / 255.0 * temperature as f32 / 255.0;
+            for (level, corrected) in levels.iter_mut().enumerate().skip(1) {
+                // Through micromath, like the worlds, which is off by a
+                // few levels even for a gamma of 1
+                let linear = if correction.gamma == 1.0 {
+                    level as f32 / 255.0
+                } else {
+                    F32Ext::powf(level as f32 / 255.0, correction.gamma)
+                };
+                let value = F32Ext::round(linear * scale * 255.0).min(255.0) as u8;
+                // Dim tones stay lit, unless the channel is off
+                *corrected = if scale > 0.0 { value.max(1) } else { 0 };
+            }
+        }
+        Lut(table)
+    }
+
+    pub fn apply(&self, color: RGB8) -> RGB8 {
+        let [r, g, b] = &self.0;
+        RGB8::new(
+            r[color.r as usize],
+            g[color.g as usize],
+            b[color.b as usize],
+        )
+    }
+}
+
+impl<'ws, Ws: PioWrite<N>, const L: usize, const N: usize> WritableMatrix
+    for LedMatrix<'ws, Ws, L, N>
+{
+    fn write(&mut self, x: usize, y: usize, color: RGB8) {
+        let index = self.index(x, y);
+        self.data[index] = color;
+    }
+
+    fn write_straight(&mut self, index: usize, color: RGB8) {
+        self.data[index] = color;
+    }
+
+    fn clear(&mut self) {
+        self.data = [RGB8::default(); N];
+    }
+
+    fn bg(&mut self, bg: RGB8) {
+        self.data = [bg; N];
+    }
+
+    fn read(&self, x: usize, y: usize) -> RGB8 {
+        let index = self.index(x, y);
+        self.data[index]
+    }
+
+    async fn flush(&mut self) {
+        let brightness = self.brightness.load(Ordering::Relaxed);
+        if brightness == u8::MAX && self.lut.is_none() && self.budget.is_none() {
+            self.ws.write(&self.data).await;
+            return;
+        }
+
+        let mut data = self.data;
+        for color in data.iter_mut() {
+            *color = dim(*color, brightness);
+            if let Some(lut) = &self.lut {
+                *color = lut.apply(*color);
+            }
+        }
+        // On what the LEDs get, the current follows their levels
+        if let Some(budget) = &self.budget {
+            let cap = budget.cap(&data, u8::MAX);
+            if cap < u8::MAX {
+                for color in data.iter_mut() {
+                    *color = dim(*color, cap);
+                }
+            }
+        }
+        self.ws.write(&data).await;
+    }
+}
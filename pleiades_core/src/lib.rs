@@ -0,0 +1,22 @@
+#![no_std]
+#![allow(async_fn_in_trait)]
+
+// This mod MUST go first, so that the others see its macros.
+pub(crate) mod fmt;
+
+pub mod als;
+pub mod apds9960;
+pub mod api;
+pub mod calibration;
+pub mod color;
+pub mod console;
+pub mod gesture;
+pub mod led_matrix;
+pub mod mqtt;
+pub mod perlin;
+pub mod proximity;
+pub mod random;
+pub mod settings;
+pub mod stream;
+pub mod swipe;
+pub mod world;
@@ -0,0 +1,418 @@
+//! MQTT 3.1.1 for the lantern. The state goes out retained under a base
+//! topic, commands come back on `set` topics and turn into the same
+//! `Command`s the gestures send, `client` keeps the session going.
+//!
+//! - `<base>/world`: name of the world, `<base>/world/set` takes a name,
+//!   `next` or `prev`
+//! - `<base>/power`: `ON` or `OFF`, `<base>/power/set` also takes `TOGGLE`
+//! - `<base>/brightness`: 0 to 255, `<base>/brightness/set` takes the same
+//! - `<base>/gesture`: events such as `swipe_left`, not retained
+//! - `<base>/availability`: `online`, `offline` once the broker loses us
+//!
+//! Only QoS 0 is used, both ways. `discovery` makes the lantern a light
+//! in Home Assistant.
+
+pub mod client;
+pub mod discovery;
+
+use crate::api::Status;
+use crate::gesture::{Command, Direction};
+use crate::world::NAMES;
+use core::fmt::Write;
+use heapless::String;
+
+/// Longest topic, base included
+pub const TOPIC_SIZE: usize = 128;
+
+pub const ONLINE: &str = "online";
+pub const OFFLINE: &str = "offline";
+
+const PROTOCOL: &str = "MQTT";
+const LEVEL: u8 = 4;
+
+const CONNECT: u8 = 1;
+const CONNACK: u8 = 2;
+const PUBLISH: u8 = 3;
+const SUBSCRIBE: u8 = 8;
+const SUBACK: u8 = 9;
+const PINGREQ: u8 = 12;
+const PINGRESP: u8 = 13;
+
+const CLEAN_SESSION: u8 = 0x02;
+const WILL: u8 = 0x04;
+const WILL_RETAIN: u8 = 0x20;
+const PASSWORD: u8 = 0x40;
+const USERNAME: u8 = 0x80;
+const RETAIN: u8 = 0x01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Packet larger than the buffer
+    Overflow,
+    /// Not a packet this client understands
+    Malformed,
+    /// `CONNACK` return code, such as 5 for not authorized
+    Refused(u8),
+    /// The broker did not grant a subscription
+    Subscription,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            Error::Overflow => defmt::write!(fmt, "Overflow"),
+            Error::Malformed => defmt::write!(fmt, "Malformed"),
+            Error::Refused(code) => defmt::write!(fmt, "Refused({})", code),
+            Error::Subscription => defmt::write!(fmt, "Subscription"),
+        }
+    }
+}
+
+/// Where and as whom the lantern talks to the broker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Config<'a> {
+    pub client_id: &'a str,
+    /// Prefix of every topic, without a trailing `/`
+    pub base: &'a str,
+    pub username: Option<&'a str>,
+    pub password: Option<&'a str>,
+    /// In seconds, the broker drops the session after 1.5 times this
+    pub keep_alive: u16,
+    /// Home Assistant discovery prefix, `None` keeps the lantern out of it
+    pub discovery: Option<&'a str>,
+}
+
+impl Default for Config<'_> {
+    fn default() -> Self {
+        Self {
+            client_id: "pleiades",
+            base: "pleiades",
+            username: None,
+            password: None,
+            keep_alive: 60,
+            discovery: Some("homeassistant"),
+        }
+    }
+}
+
+impl Config<'_> {
+    /// `<base>/<leaf>`
+    pub fn topic(&self, leaf: &str) -> Result<String<TOPIC_SIZE>, Error> {
+        let mut topic = String::new();
+        write!(topic, "{}/{}", self.base, leaf).map_err(|_| Error::Overflow)?;
+        Ok(topic)
+    }
+
+    /// `CONNECT` with `offline` as the will on `<base>/availability`
+    pub fn connect<'c>(&'c self, availability: &'c str) -> Connect<'c> {
+        Connect {
+            client_id: self.client_id,
+            keep_alive: self.keep_alive,
+            will: Some(Message {
+                topic: availability,
+                payload: OFFLINE.as_bytes(),
+                retain: true,
+            }),
+            username: self.username,
+            password: self.password,
+        }
+    }
+
+    /// The command a message on one of the `set` topics asks for
+    pub fn command(&self, message: &Message) -> Option<Command> {
+        let leaf = message.topic.strip_prefix(self.base)?.strip_prefix('/')?;
+        let payload = core::str::from_utf8(message.payload).ok()?.trim();
+
+        match leaf {
+            "world/set" => match payload {
+                "next" => Some(Command::Swipe(Direction::Right)),
+                "prev" => Some(Command::Swipe(Direction::Left)),
+                name => NAMES
+                    .iter()
+                    .position(|world| *world == name)
+                    .map(Command::World),
+            },
+            "brightness/set" => payload.parse().ok().map(Command::Brightness),
+            "power/set" => match payload {
+                "ON" => Some(Command::Power(true)),
+                "OFF" => Some(Command::Power(false)),
+                "TOGGLE" => Some(Command::SwitchPower),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Payload of `<base>/world`
+pub fn world(status: Status) -> &'static str {
+    NAMES.get(status.world).copied().unwrap_or_default()
+}
+
+/// Payload of `<base>/power`
+pub fn power(status: Status) -> &'static str {
+    match status.on {
+        true => "ON",
+        false => "OFF",
+    }
+}
+
+/// Payload of `<base>/brightness`
+pub fn brightness(status: Status) -> String<3> {
+    let mut payload = String::new();
+    // Fits, at most 255
+    let _ = write!(payload, "{}", status.brightness);
+    payload
+}
+
+/// Payload of `<base>/gesture`, `None` for the commands that only
+/// come from the remotes
+pub fn gesture(command: Command) -> Option<&'static str> {
+    let event = match command {
+        Command::Swing => "swing",
+        Command::SwitchPower => "power",
+        Command::Calibrate => "calibrate",
+        Command::Level(Direction::Up) => "up",
+        Command::Level(Direction::Down) => "down",
+        Command::Swipe(Direction::Up) => "swipe_up",
+        Command::Swipe(Direction::Down) => "swipe_down",
+        Command::Swipe(Direction::Left) => "swipe_left",
+        Command::Swipe(Direction::Right) => "swipe_right",
+        Command::Swipe(Direction::Near) => "swipe_near",
+        Command::Swipe(Direction::Far) => "swipe_far",
+        Command::Level(Direction::Left | Direction::Right | Direction::Near | Direction::Far)
+        | Command::Power(_)
+        | Command::World(_)
+        | Command::Brightness(_) => return None,
+    };
+    Some(event)
+}
+
+/// An application message, QoS 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Message<'a> {
+    pub topic: &'a str,
+    pub payload: &'a [u8],
+    pub retain: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Connect<'a> {
+    pub client_id: &'a str,
+    pub keep_alive: u16,
+    /// Published by the broker when the connection drops
+    pub will: Option<Message<'a>>,
+    pub username: Option<&'a str>,
+    pub password: Option<&'a str>,
+}
+
+/// A packet from the broker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Packet<'a> {
+    ConnAck {
+        session_present: bool,
+        code: u8,
+    },
+    Publish(Message<'a>),
+    SubAck {
+        id: u16,
+        codes: &'a [u8],
+    },
+    PingResp,
+    /// Anything else, by packet type
+    Other(u8),
+}
+
+/// Length of the packet at the start of `buffer`, `None` until it is all
+/// there
+pub fn frame(buffer: &[u8]) -> Result<Option<usize>, Error> {
+    let mut length = 0;
+    for (i, byte) in buffer.iter().skip(1).take(4).enumerate() {
+        length |= ((byte & 0x7F) as usize) << (7 * i);
+        if byte & 0x80 == 0 {
+            let total = 1 + i + 1 + length;
+            return Ok((buffer.len() >= total).then_some(total));
+        }
+    }
+    match buffer.len() > 4 {
+        true => Err(Error::Malformed),
+        false => Ok(None),
+    }
+}
+
+/// The packet `frame` found at the start of `buffer`
+pub fn decode(buffer: &[u8]) -> Result<Packet<'_>, Error> {
+    let total = frame(buffer)?.ok_or(Error::Malformed)?;
+    let header = buffer[0];
+    let start = (1..5)
+        .find(|i| buffer[*i] & 0x80 == 0)
+        .ok_or(Error::Malformed)?
+        + 1;
+    let body = &buffer[start..total];
+
+    match header >> 4 {
+        CONNACK => match body {
+            [flags, code] => Ok(Packet::ConnAck {
+                session_present: flags & 0x01 != 0,
+                code: *code,
+            }),
+            _ => Err(Error::Malformed),
+        },
+        PUBLISH => {
+            let (topic, mut rest) = string(body)?;
+            // QoS 1 and 2 carry a packet id
+            if header & 0x06 != 0 {
+                rest = rest.get(2..).ok_or(Error::Malformed)?;
+            }
+            Ok(Packet::Publish(Message {
+                topic,
+                payload: rest,
+                retain: header & RETAIN != 0,
+            }))
+        }
+        SUBACK => match body {
+            [high, low, codes @ ..] => Ok(Packet::SubAck {
+                id: u16::from_be_bytes([*high, *low]),
+                codes,
+            }),
+            _ => Err(Error::Malformed),
+        },
+        PINGRESP => Ok(Packet::PingResp),
+        other => Ok(Packet::Other(other)),
+    }
+}
+
+fn string(bytes: &[u8]) -> Result<(&str, &[u8]), Error> {
+    let [high, low, rest @ ..] = bytes else {
+        return Err(Error::Malformed);
+    };
+    let length = u16::from_be_bytes([*high, *low]) as usize;
+    if rest.len() < length {
+        return Err(Error::Malformed);
+    }
+    let text = core::str::from_utf8(&rest[..length]).map_err(|_| Error::Malformed)?;
+    Ok((text, &rest[length..]))
+}
+
+/// Packets written into a buffer
+struct Writer<'b> {
+    buffer: &'b mut [u8],
+    len: usize,
+}
+
+impl<'b> Writer<'b> {
+    fn new(buffer: &'b mut [u8]) -> Self {
+        Writer { buffer, len: 0 }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let end = self.len + bytes.len();
+        self.buffer
+            .get_mut(self.len..end)
+            .ok_or(Error::Overflow)?
+            .copy_from_slice(bytes);
+        self.len = end;
+        Ok(())
+    }
+
+    fn u16(&mut self, value: u16) -> Result<(), Error> {
+        self.bytes(&value.to_be_bytes())
+    }
+
+    fn string(&mut self, text: &[u8]) -> Result<(), Error> {
+        let length = u16::try_from(text.len()).map_err(|_| Error::Overflow)?;
+        self.u16(length)?;
+        self.bytes(text)
+    }
+
+    /// Fixed header for a packet whose rest is `length` long
+    fn header(&mut self, header: u8, mut length: usize) -> Result<(), Error> {
+        self.bytes(&[header])?;
+        loop {
+            let mut byte = (length & 0x7F) as u8;
+            length >>= 7;
+            if length > 0 {
+                byte |= 0x80;
+            }
+            self.bytes(&[byte])?;
+            if length == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn string_length(text: &[u8]) -> usize {
+    2 + text.len()
+}
+
+/// Encode `CONNECT` into `buffer`, returns its length
+pub fn connect(buffer: &mut [u8], connect: &Connect) -> Result<usize, Error> {
+    let mut flags = CLEAN_SESSION;
+    let mut length = string_length(PROTOCOL.as_bytes()) + 4;
+    length += string_length(connect.client_id.as_bytes());
+    if let Some(will) = connect.will {
+        flags |= WILL;
+        if will.retain {
+            flags |= WILL_RETAIN;
+        }
+        length += string_length(will.topic.as_bytes()) + string_length(will.payload);
+    }
+    if let Some(username) = connect.username {
+        flags |= USERNAME;
+        length += string_length(username.as_bytes());
+    }
+    if let Some(password) = connect.password {
+        flags |= PASSWORD;
+        length += string_length(password.as_bytes());
+    }
+
+    let mut writer = Writer::new(buffer);
+    writer.header(CONNECT << 4, length)?;
+    writer.string(PROTOCOL.as_bytes())?;
+    writer.bytes(&[LEVEL, flags])?;
+    writer.u16(connect.keep_alive)?;
+    writer.string(connect.client_id.as_bytes())?;
+    if let Some(will) = connect.will {
+        writer.string(will.topic.as_bytes())?;
+        writer.string(will.payload)?;
+    }
+    if let Some(username) = connect.username {
+        writer.string(username.as_bytes())?;
+    }
+    if let Some(password) = connect.password {
+        writer.string(password.as_bytes())?;
+    }
+    Ok(writer.len)
+}
+
+/// Encode `PUBLISH` at QoS 0 into `buffer`, returns its length
+pub fn publish(buffer: &mut [u8], message: &Message) -> Result<usize, Error> {
+    let length = string_length(message.topic.as_bytes()) + message.payload.len();
+    let mut writer = Writer::new(buffer);
+    writer.header(PUBLISH << 4 | message.retain as u8, length)?;
+    writer.string(message.topic.as_bytes())?;
+    writer.bytes(message.payload)?;
+    Ok(writer.len)
+}
+
+/// Encode `SUBSCRIBE` to `filters` at QoS 0 into `buffer`, returns its
+/// length
+pub fn subscribe(buffer: &mut [u8], id: u16, filters: &[&str]) -> Result<usize, Error> {
+    let length = 2 + filters
+        .iter()
+        .map(|filter| string_length(filter.as_bytes()) + 1)
+        .sum::<usize>();
+    let mut writer = Writer::new(buffer);
+    writer.header(SUBSCRIBE << 4 | 0x02, length)?;
+    writer.u16(id)?;
+    for filter in filters {
+        writer.string(filter.as_bytes())?;
+        writer.bytes(&[0])?;
+    }
+    Ok(writer.len)
+}
+
+/// `PINGREQ`
+pub const PING: [u8; 2] = [PINGREQ << 4, 0];
@@ -0,0 +1,368 @@
+//! MQTT client over any byte stream, `run` keeps the lantern's session
+//! going over an embassy-net stack
+
+use super::{discovery, Config, Message, Packet, ONLINE, TOPIC_SIZE};
+use crate::api::{Lantern, Status};
+use crate::gesture::Command;
+use embassy_futures::select::{select3, Either3};
+use embassy_time::{Duration, Instant, Ticker};
+use embedded_io_async::{Read, Write};
+use heapless::String;
+
+/// Largest packet either way, fits the discovery payload and its topic
+pub const PACKET_SIZE: usize = 1280;
+/// How often the status is checked for changes
+const STATUS_PERIOD: Duration = Duration::from_millis(500);
+
+/// The lantern as the MQTT client sees it
+pub trait Gestures: Lantern {
+    /// Wait for the next command a gesture gave
+    async fn gesture(&self) -> Command;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    Io(E),
+    Mqtt(super::Error),
+    /// The broker closed the connection
+    Closed,
+    /// No answer to a ping within the keep alive
+    Timeout,
+}
+
+impl<E> From<super::Error> for Error<E> {
+    fn from(err: super::Error) -> Self {
+        Error::Mqtt(err)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<E: defmt::Format> defmt::Format for Error<E> {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            Error::Io(err) => defmt::write!(fmt, "Io({})", err),
+            Error::Mqtt(err) => defmt::write!(fmt, "Mqtt({})", err),
+            Error::Closed => defmt::write!(fmt, "Closed"),
+            Error::Timeout => defmt::write!(fmt, "Timeout"),
+        }
+    }
+}
+
+pub struct Client<'b, T> {
+    transport: T,
+    rx: &'b mut [u8],
+    tx: &'b mut [u8],
+    // Bytes received, and the part of them already handed out
+    len: usize,
+    consumed: usize,
+    id: u16,
+    last_write: Instant,
+    last_read: Instant,
+}
+
+impl<'b, T: Read + Write> Client<'b, T> {
+    pub fn new(transport: T, rx: &'b mut [u8], tx: &'b mut [u8]) -> Self {
+        Client {
+            transport,
+            rx,
+            tx,
+            len: 0,
+            consumed: 0,
+            id: 0,
+            last_write: Instant::now(),
+            last_read: Instant::now(),
+        }
+    }
+
+    /// Send `CONNECT` and wait for the broker to accept it
+    pub async fn connect(&mut self, connect: &super::Connect<'_>) -> Result<(), Error<T::Error>> {
+        let len = super::connect(self.tx, connect)?;
+        self.send(len).await?;
+        loop {
+            if let Packet::ConnAck { code, .. } = self.receive().await? {
+                return match code {
+                    0 => Ok(()),
+                    code => Err(super::Error::Refused(code).into()),
+                };
+            }
+        }
+    }
+
+    /// Subscribe to `filters` at QoS 0 and wait for the broker to grant
+    /// them. Messages that arrive in between are dropped.
+    pub async fn subscribe(&mut self, filters: &[&str]) -> Result<(), Error<T::Error>> {
+        self.id = self.id.wrapping_add(1).max(1);
+        let expected = self.id;
+        let len = super::subscribe(self.tx, expected, filters)?;
+        self.send(len).await?;
+        loop {
+            if let Packet::SubAck { id, codes } = self.receive().await? {
+                if id != expected {
+                    continue;
+                }
+                return match codes.iter().all(|code| *code & 0x80 == 0) {
+                    true => Ok(()),
+                    false => Err(super::Error::Subscription.into()),
+                };
+            }
+        }
+    }
+
+    pub async fn publish(&mut self, message: &Message<'_>) -> Result<(), Error<T::Error>> {
+        let len = super::publish(self.tx, message)?;
+        self.send(len).await
+    }
+
+    pub async fn ping(&mut self) -> Result<(), Error<T::Error>> {
+        self.tx[..super::PING.len()].copy_from_slice(&super::PING);
+        self.send(super::PING.len()).await
+    }
+
+    /// Time since the last packet went out
+    pub fn idle(&self) -> Duration {
+        Instant::now() - self.last_write
+    }
+
+    /// Time since the broker last sent anything
+    pub fn silent(&self) -> Duration {
+        Instant::now() - self.last_read
+    }
+
+    /// Read once from the transport. Cancel safe, nothing is lost when the
+    /// future is dropped.
+    pub async fn fill(&mut self) -> Result<(), Error<T::Error>> {
+        self.compact();
+        if self.len == self.rx.len() {
+            return Err(super::Error::Overflow.into());
+        }
+        let read = self
+            .transport
+            .read(&mut self.rx[self.len..])
+            .await
+            .map_err(Error::Io)?;
+        if read == 0 {
+            return Err(Error::Closed);
+        }
+        self.len += read;
+        self.last_read = Instant::now();
+        Ok(())
+    }
+
+    /// Next packet already received, if any
+    pub fn packet(&mut self) -> Result<Option<Packet<'_>>, Error<T::Error>> {
+        self.compact();
+        match super::frame(&self.rx[..self.len])? {
+            Some(total) => {
+                self.consumed = total;
+                Ok(Some(super::decode(&self.rx[..total])?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Wait for the next packet
+    pub async fn receive(&mut self) -> Result<Packet<'_>, Error<T::Error>> {
+        self.compact();
+        while super::frame(&self.rx[..self.len])?.is_none() {
+            self.fill().await?;
+        }
+        // Complete now
+        Ok(self.packet()?.unwrap_or(Packet::Other(0)))
+    }
+
+    fn compact(&mut self) {
+        if self.consumed > 0 {
+            self.rx.copy_within(self.consumed..self.len, 0);
+            self.len -= self.consumed;
+            self.consumed = 0;
+        }
+    }
+
+    async fn send(&mut self, len: usize) -> Result<(), Error<T::Error>> {
+        self.transport
+            .write_all(&self.tx[..len])
+            .await
+            .map_err(Error::Io)?;
+        self.transport.flush().await.map_err(Error::Io)?;
+        self.last_write = Instant::now();
+        Ok(())
+    }
+}
+
+/// Connect, announce the lantern to Home Assistant, publish the state as
+/// it changes along with the gestures, and pass on the commands until the
+/// connection fails
+pub async fn session<T: Read + Write>(
+    client: &mut Client<'_, T>,
+    config: &Config<'_>,
+    lantern: &impl Gestures,
+) -> Result<(), Error<T::Error>> {
+    let topic = |leaf| config.topic(leaf);
+    let availability = topic("availability")?;
+    client.connect(&config.connect(&availability)).await?;
+    let (world, brightness, power) = (
+        topic("world/set")?,
+        topic("brightness/set")?,
+        topic("power/set")?,
+    );
+    match discovery::status_topic(config)? {
+        Some(status) => {
+            client
+                .subscribe(&[&world, &brightness, &power, &status])
+                .await?
+        }
+        None => client.subscribe(&[&world, &brightness, &power]).await?,
+    }
+    announce(client, config).await?;
+    publish(client, &availability, ONLINE.as_bytes(), true).await?;
+
+    let keep_alive = Duration::from_secs(config.keep_alive.into());
+    let mut published: Option<Status> = None;
+    let mut ticker = Ticker::every(STATUS_PERIOD);
+
+    loop {
+        let status = lantern.status();
+        if published.map(|old| old.world) != Some(status.world) {
+            publish(
+                client,
+                &topic("world")?,
+                super::world(status).as_bytes(),
+                true,
+            )
+            .await?;
+        }
+        if published.map(|old| old.on) != Some(status.on) {
+            publish(
+                client,
+                &topic("power")?,
+                super::power(status).as_bytes(),
+                true,
+            )
+            .await?;
+        }
+        if published.map(|old| old.brightness) != Some(status.brightness) {
+            let brightness = super::brightness(status);
+            publish(client, &topic("brightness")?, brightness.as_bytes(), true).await?;
+        }
+        published = Some(status);
+
+        match select3(client.fill(), lantern.gesture(), ticker.next()).await {
+            Either3::First(read) => {
+                read?;
+                let mut birth = false;
+                loop {
+                    let command = match client.packet()? {
+                        Some(Packet::Publish(message)) => {
+                            birth |= discovery::is_birth(config, &message);
+                            config.command(&message)
+                        }
+                        Some(_) => None,
+                        None => break,
+                    };
+                    if let Some(command) = command {
+                        lantern.send(command).await;
+                    }
+                }
+                if birth {
+                    announce(client, config).await?;
+                }
+            }
+            Either3::Second(command) => {
+                if let Some(event) = super::gesture(command) {
+                    publish(client, &topic("gesture")?, event.as_bytes(), false).await?;
+                }
+            }
+            Either3::Third(()) => {
+                if keep_alive.as_ticks() > 0 {
+                    if client.silent() > keep_alive + keep_alive / 2 {
+                        return Err(Error::Timeout);
+                    }
+                    if client.idle() >= keep_alive / 2 {
+                        client.ping().await?;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Publish the Home Assistant discovery payload, retained
+async fn announce<T: Read + Write>(
+    client: &mut Client<'_, T>,
+    config: &Config<'_>,
+) -> Result<(), Error<T::Error>> {
+    match discovery::topic(config)? {
+        Some(topic) => {
+            let payload = discovery::payload(config)?;
+            publish(client, &topic, payload.as_bytes(), true).await
+        }
+        None => Ok(()),
+    }
+}
+
+async fn publish<T: Read + Write>(
+    client: &mut Client<'_, T>,
+    topic: &String<TOPIC_SIZE>,
+    payload: &[u8],
+    retain: bool,
+) -> Result<(), Error<T::Error>> {
+    client
+        .publish(&Message {
+            topic,
+            payload,
+            retain,
+        })
+        .await
+}
+
+#[cfg(feature = "net")]
+mod net {
+    use super::{session, Client, Gestures, PACKET_SIZE};
+    use crate::mqtt::Config;
+    use embassy_net::driver::Driver;
+    use embassy_net::tcp::TcpSocket;
+    use embassy_net::{IpEndpoint, Stack};
+    use embassy_time::{Duration, Timer};
+
+    /// Wait before connecting again
+    const RETRY: Duration = Duration::from_secs(5);
+
+    /// Keep a session with the broker at `broker`, reconnecting whenever
+    /// it fails
+    pub async fn run<D: Driver>(
+        stack: &Stack<D>,
+        broker: IpEndpoint,
+        config: &Config<'_>,
+        lantern: &impl Gestures,
+    ) -> ! {
+        let mut socket_rx = [0u8; PACKET_SIZE];
+        let mut socket_tx = [0u8; PACKET_SIZE];
+        let mut rx = [0u8; PACKET_SIZE];
+        let mut tx = [0u8; PACKET_SIZE];
+
+        loop {
+            stack.wait_config_up().await;
+            let mut socket = TcpSocket::new(stack, &mut socket_rx, &mut socket_tx);
+            let keep_alive = Duration::from_secs(config.keep_alive.into());
+            if keep_alive.as_ticks() > 0 {
+                socket.set_timeout(Some(keep_alive * 2));
+            }
+
+            match socket.connect(broker).await {
+                Ok(()) => {
+                    let mut client = Client::new(&mut socket, &mut rx, &mut tx);
+                    if let Err(err) = session(&mut client, config, lantern).await {
+                        error!("MQTT session failed: {:?}", err);
+                    }
+                }
+                Err(err) => error!("MQTT connect failed: {:?}", err),
+            }
+            socket.abort();
+            let _ = socket.flush().await;
+            Timer::after(RETRY).await;
+        }
+    }
+}
+
+#[cfg(feature = "net")]
+pub use net::run;
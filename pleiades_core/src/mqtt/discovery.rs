@@ -0,0 +1,89 @@
+//! Home Assistant MQTT discovery. The lantern shows up as a light with
+//! on/off, brightness and the worlds as effects, on the topics of `mqtt`.
+
+use super::{Config, Error, Message, TOPIC_SIZE};
+use crate::world::NAMES;
+use core::fmt::Write;
+use heapless::String;
+use serde::Serialize;
+
+/// Largest discovery payload
+pub const PAYLOAD_SIZE: usize = 1024;
+/// Home Assistant announces itself with this on `<prefix>/status`
+const BIRTH: &[u8] = b"online";
+
+#[derive(Serialize)]
+struct Light<'a> {
+    /// `null` names the entity after the device
+    name: Option<&'a str>,
+    unique_id: &'a str,
+    availability_topic: &'a str,
+    command_topic: &'a str,
+    state_topic: &'a str,
+    brightness_command_topic: &'a str,
+    brightness_state_topic: &'a str,
+    brightness_scale: u8,
+    effect_command_topic: &'a str,
+    effect_state_topic: &'a str,
+    effect_list: &'a [&'a str],
+    device: Device<'a>,
+}
+
+#[derive(Serialize)]
+struct Device<'a> {
+    identifiers: [&'a str; 1],
+    name: &'a str,
+    model: &'a str,
+}
+
+/// `<prefix>/light/<client id>/config`, `None` without discovery
+pub fn topic(config: &Config) -> Result<Option<String<TOPIC_SIZE>>, Error> {
+    let Some(prefix) = config.discovery else {
+        return Ok(None);
+    };
+    let mut topic = String::new();
+    write!(topic, "{}/light/{}/config", prefix, config.client_id).map_err(|_| Error::Overflow)?;
+    Ok(Some(topic))
+}
+
+/// `<prefix>/status`, where Home Assistant says it (re)started
+pub fn status_topic(config: &Config) -> Result<Option<String<TOPIC_SIZE>>, Error> {
+    let Some(prefix) = config.discovery else {
+        return Ok(None);
+    };
+    let mut topic = String::new();
+    write!(topic, "{}/status", prefix).map_err(|_| Error::Overflow)?;
+    Ok(Some(topic))
+}
+
+/// Home Assistant came back and forgot the lantern, send the payload again
+pub fn is_birth(config: &Config, message: &Message) -> bool {
+    match status_topic(config) {
+        Ok(Some(topic)) => message.topic == topic.as_str() && message.payload == BIRTH,
+        _ => false,
+    }
+}
+
+/// Discovery payload of the light, effects from `world::NAMES`
+pub fn payload(config: &Config) -> Result<String<PAYLOAD_SIZE>, Error> {
+    let topic = |leaf| config.topic(leaf);
+    let light = Light {
+        name: None,
+        unique_id: config.client_id,
+        availability_topic: &topic("availability")?,
+        command_topic: &topic("power/set")?,
+        state_topic: &topic("power")?,
+        brightness_command_topic: &topic("brightness/set")?,
+        brightness_state_topic: &topic("brightness")?,
+        brightness_scale: u8::MAX,
+        effect_command_topic: &topic("world/set")?,
+        effect_state_topic: &topic("world")?,
+        effect_list: &NAMES,
+        device: Device {
+            identifiers: [config.client_id],
+            name: "Pleiades",
+            model: "Pleiades lantern",
+        },
+    };
+    serde_json_core::to_string(&light).map_err(|_| Error::Overflow)
+}
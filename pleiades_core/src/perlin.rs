@@ -1,10 +1,7 @@
 // Taken from https://github.com/Lapz/perlin_noise.git
 
-use embassy_rp::clocks::RoscRng;
-use heapless::Vec;
+use crate::random::RandomSource;
 use micromath::F32Ext;
-use rand::RngCore;
-use rand::{seq::SliceRandom, Rng};
 
 /// Perlin Noise generator that outputs 1/2/3D Perlin noise
 #[derive(Clone)]
@@ -14,16 +11,8 @@ pub struct PerlinNoise {
     fallout: f32,
 }
 
-impl Default for PerlinNoise {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
 impl PerlinNoise {
-    pub fn new() -> PerlinNoise {
-        let mut rng = RoscRng;
-
+    pub fn new(rng: &mut impl RandomSource) -> PerlinNoise {
         let mut perm = [0; 512];
 
         for (i, p) in perm.iter_mut().enumerate().take(256) {
@@ -31,7 +20,7 @@ impl PerlinNoise {
         }
 
         for i in 0..256 {
-            let j = rng.gen_range(0..256) & 0xFF;
+            let j = rng.rand_uint(0, 256) as usize & 0xFF;
 
             perm.swap(j, i);
         }
@@ -92,14 +81,16 @@ impl PerlinNoise {
         sum
     }
 
+    // Math goes through micromath explicitly, so the host
+    // does not pick std's float methods and frames match the device.
     fn noise3d(&self, mut x: f32, mut y: f32, mut z: f32) -> f32 {
-        let x0 = (x.floor() as usize) & 255;
-        let y0 = (y.floor() as usize) & 255;
-        let z0 = (z.floor() as usize) & 255;
+        let x0 = (F32Ext::floor(x) as usize) & 255;
+        let y0 = (F32Ext::floor(y) as usize) & 255;
+        let z0 = (F32Ext::floor(z) as usize) & 255;
 
-        x -= x.floor();
-        y -= y.floor();
-        z -= z.floor();
+        x -= F32Ext::floor(x);
+        y -= F32Ext::floor(y);
+        z -= F32Ext::floor(z);
 
         let fx = (3.0 - 2.0 * x) * x * x;
         let fy = (3.0 - 2.0 * y) * y * y;
@@ -144,11 +135,11 @@ impl PerlinNoise {
     }
 
     fn noise2d(&self, mut x: f32, mut y: f32) -> f32 {
-        let x0 = (x.floor() as usize) & 255;
-        let y0 = (y.floor() as usize) & 255;
+        let x0 = (F32Ext::floor(x) as usize) & 255;
+        let y0 = (F32Ext::floor(y) as usize) & 255;
 
-        x -= x.floor();
-        y -= y.floor();
+        x -= F32Ext::floor(x);
+        y -= F32Ext::floor(y);
 
         let fx = (3.0 - 2.0 * x) * x * x;
         let fy = (3.0 - 2.0 * y) * y * y;
@@ -171,9 +162,9 @@ impl PerlinNoise {
     }
 
     fn noise1d(&self, mut x: f32) -> f32 {
-        let x0 = (x.floor() as usize) & 255;
+        let x0 = (F32Ext::floor(x) as usize) & 255;
 
-        x -= x.floor();
+        x -= F32Ext::floor(x);
 
         let fx = (3.0 - 2.0 * x) * x * x;
         lerp(
@@ -229,33 +220,3 @@ fn lerp(t: f32, a: f32, b: f32) -> f32 {
 // Fade function as defined by Ken Perlin.  This eases coordinate values
 // so that they will "ease" towards integral values.  This ends up smoothing
 // the final output.
-
-pub fn rand_float(min: f32, max: f32) -> f32 {
-    let mut rng = RoscRng;
-    rng.gen_range(min..max)
-}
-
-pub fn rand_uint(min: u32, max: u32) -> u32 {
-    let mut rng = RoscRng;
-    rng.gen_range(min..max)
-}
-
-pub fn rand_int(min: i32, max: i32) -> i32 {
-    let mut rng = RoscRng;
-    rng.gen_range(min..max)
-}
-
-pub fn fair_rand_float() -> f32 {
-    let mut rng = RoscRng;
-    (rng.next_u64() as f32) / (u64::MAX as f32)
-}
-
-pub fn spawn_chance(numerator: u32, denominator: u32) -> bool {
-    let mut rng = RoscRng;
-    rng.gen_ratio(numerator, denominator)
-}
-
-pub fn shuffle<T, const N: usize>(vec: &mut Vec<T, N>) {
-    let mut rng = RoscRng;
-    vec.shuffle(&mut rng);
-}
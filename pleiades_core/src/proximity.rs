@@ -0,0 +1,204 @@
+/// Length of one wait cycle, in ms
+const CYCLE_MS: f32 = 2.78;
+/// `WLONG` stretches every wait cycle by this factor
+const LONG_FACTOR: f32 = 12.0;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ProximityGain {
+    #[default]
+    X1,
+    X2,
+    X4,
+    X8,
+}
+
+impl ProximityGain {
+    /// `PGAIN` field of the `CONTROL` register
+    pub fn bits(&self) -> u8 {
+        match self {
+            ProximityGain::X1 => 0b00,
+            ProximityGain::X2 => 0b01,
+            ProximityGain::X4 => 0b10,
+            ProximityGain::X8 => 0b11,
+        }
+    }
+}
+
+/// Current through the IR LED while it pulses
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LedDrive {
+    #[default]
+    Ma100,
+    Ma50,
+    Ma25,
+    Ma12_5,
+}
+
+impl LedDrive {
+    /// `LDRIVE` field of the `CONTROL` register
+    pub fn bits(&self) -> u8 {
+        match self {
+            LedDrive::Ma100 => 0b00,
+            LedDrive::Ma50 => 0b01,
+            LedDrive::Ma25 => 0b10,
+            LedDrive::Ma12_5 => 0b11,
+        }
+    }
+
+    pub fn ma(&self) -> f32 {
+        match self {
+            LedDrive::Ma100 => 100.0,
+            LedDrive::Ma50 => 50.0,
+            LedDrive::Ma25 => 25.0,
+            LedDrive::Ma12_5 => 12.5,
+        }
+    }
+}
+
+/// Extra LED current on top of `LedDrive`, shared with the gesture engine
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LedBoost {
+    X1,
+    X1_5,
+    X2,
+    #[default]
+    X3,
+}
+
+impl LedBoost {
+    /// `LED_BOOST` field of the `CONFIG2` register
+    pub fn bits(&self) -> u8 {
+        match self {
+            LedBoost::X1 => 0b00,
+            LedBoost::X1_5 => 0b01,
+            LedBoost::X2 => 0b10,
+            LedBoost::X3 => 0b11,
+        }
+    }
+
+    pub fn factor(&self) -> f32 {
+        match self {
+            LedBoost::X1 => 1.0,
+            LedBoost::X1_5 => 1.5,
+            LedBoost::X2 => 2.0,
+            LedBoost::X3 => 3.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PulseLength {
+    Us4,
+    #[default]
+    Us8,
+    Us16,
+    Us32,
+}
+
+impl PulseLength {
+    /// `PPLEN` field of the `PPULSE` register
+    pub fn bits(&self) -> u8 {
+        match self {
+            PulseLength::Us4 => 0b00,
+            PulseLength::Us8 => 0b01,
+            PulseLength::Us16 => 0b10,
+            PulseLength::Us32 => 0b11,
+        }
+    }
+}
+
+/// Pause between two proximity cycles, saves power at the cost of
+/// fewer readings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WaitTime {
+    /// Wait cycles, from 1 to 256
+    pub cycles: u16,
+    /// Twelve times longer cycles
+    pub long: bool,
+}
+
+impl WaitTime {
+    /// Value of the `WTIME` register
+    pub fn wtime(&self) -> u8 {
+        (256 - self.cycles.clamp(1, 256)) as u8
+    }
+
+    pub fn ms(&self) -> f32 {
+        let factor = match self.long {
+            true => LONG_FACTOR,
+            false => 1.0,
+        };
+        self.cycles.clamp(1, 256) as f32 * CYCLE_MS * factor
+    }
+}
+
+/// Value out of the range the datasheet allows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigError {
+    /// Pulses per cycle, from 1 to 64
+    Pulses(u8),
+    /// Wait cycles, from 1 to 256
+    WaitCycles(u16),
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for ConfigError {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            ConfigError::Pulses(pulses) => defmt::write!(fmt, "Pulses({})", pulses),
+            ConfigError::WaitCycles(cycles) => defmt::write!(fmt, "WaitCycles({})", cycles),
+        }
+    }
+}
+
+/// Range against power draw of the proximity measurement. More gain, LED
+/// current and pulses see a hand further away through a thicker diffuser.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProximityConfig {
+    pub gain: ProximityGain,
+    pub led_drive: LedDrive,
+    pub led_boost: LedBoost,
+    pub pulse_length: PulseLength,
+    /// Pulses per proximity cycle, from 1 to 64
+    pub pulses: u8,
+    /// `None` runs the proximity cycles back to back
+    pub wait: Option<WaitTime>,
+}
+
+impl Default for ProximityConfig {
+    fn default() -> Self {
+        // Register values the gesture thresholds were tuned with
+        Self {
+            gain: ProximityGain::X1,
+            led_drive: LedDrive::Ma100,
+            led_boost: LedBoost::X3,
+            pulse_length: PulseLength::Us8,
+            pulses: 1,
+            wait: None,
+        }
+    }
+}
+
+impl ProximityConfig {
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(1..=64).contains(&self.pulses) {
+            return Err(ConfigError::Pulses(self.pulses));
+        }
+        match self.wait {
+            Some(wait) if !(1..=256).contains(&wait.cycles) => {
+                Err(ConfigError::WaitCycles(wait.cycles))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Value of the `PPULSE` register
+    pub fn ppulse(&self) -> u8 {
+        self.pulse_length.bits() << 6 | (self.pulses.clamp(1, 64) - 1)
+    }
+
+    /// Peak current through the IR LED, in mA
+    pub fn led_current_ma(&self) -> f32 {
+        self.led_drive.ma() * self.led_boost.factor()
+    }
+}
@@ -0,0 +1,72 @@
+use heapless::Vec;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_pcg::Pcg32;
+
+/// Randomness for the noise and the worlds.
+/// Any `RngCore` is a source, including the hardware `RoscRng`.
+pub trait RandomSource: RngCore {
+    fn rand_float(&mut self, min: f32, max: f32) -> f32 {
+        self.gen_range(min..max)
+    }
+
+    fn rand_uint(&mut self, min: u32, max: u32) -> u32 {
+        self.gen_range(min..max)
+    }
+
+    fn rand_int(&mut self, min: i32, max: i32) -> i32 {
+        self.gen_range(min..max)
+    }
+
+    fn fair_rand_float(&mut self) -> f32 {
+        (self.next_u64() as f32) / (u64::MAX as f32)
+    }
+
+    fn spawn_chance(&mut self, numerator: u32, denominator: u32) -> bool {
+        self.gen_ratio(numerator, denominator)
+    }
+
+    fn shuffle<T, const N: usize>(&mut self, vec: &mut Vec<T, N>) {
+        vec.shuffle(self);
+    }
+}
+
+impl<R: RngCore> RandomSource for R {}
+
+/// Seedable generator. PCG is word size independent,
+/// so one seed gives the same frames on the device and on the host.
+pub struct SeededRandom {
+    seed: u64,
+    rng: Pcg32,
+}
+
+impl SeededRandom {
+    pub fn new(seed: u64) -> Self {
+        SeededRandom {
+            seed,
+            rng: Pcg32::seed_from_u64(seed),
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl RngCore for SeededRandom {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
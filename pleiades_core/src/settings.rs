@@ -0,0 +1,116 @@
+//! Settings that survive a power cycle: the world shown, the power, the
+//! brightness, the parameter of every world and the proximity offset.
+//! They live in the key/value `store` on any NOR flash, `mock` keeps them
+//! in RAM.
+
+pub mod mock;
+pub mod store;
+
+use crate::api::Status;
+use crate::world::{Parameter, NAMES, WORLDS};
+use embedded_storage::nor_flash::NorFlash;
+pub use store::{Error, Store};
+
+const WORLD: u8 = 1;
+const POWER: u8 = 2;
+const BRIGHTNESS: u8 = 3;
+const PROXIMITY_OFFSET: u8 = 4;
+/// One after the other in the order of `NAMES`
+const PARAMETERS: u8 = 16;
+
+/// What the lantern starts with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct State {
+    pub status: Status,
+    /// Of the worlds in `NAMES`, `None` until changed
+    pub parameters: [Option<Parameter>; WORLDS],
+}
+
+impl Default for State {
+    fn default() -> Self {
+        // The first world, on, at full brightness
+        State {
+            status: Status {
+                world: 0,
+                on: true,
+                brightness: u8::MAX,
+            },
+            parameters: [None; WORLDS],
+        }
+    }
+}
+
+pub struct Settings<F: NorFlash> {
+    store: Store<F>,
+}
+
+impl<F: NorFlash> Settings<F> {
+    pub fn new(store: Store<F>) -> Self {
+        Settings { store }
+    }
+
+    /// Saved state, the defaults for what was never saved
+    pub fn state(&self) -> State {
+        let byte = |key| match self.store.get(key) {
+            Some([value]) => Some(*value),
+            _ => None,
+        };
+        let default = State::default();
+
+        let mut parameters = [None; WORLDS];
+        for (index, parameter) in parameters.iter_mut().enumerate() {
+            *parameter = match self.store.get(PARAMETERS + index as u8) {
+                Some([value, shade]) => Some(Parameter {
+                    value: *value,
+                    shade: *shade as i8,
+                }),
+                _ => None,
+            };
+        }
+
+        State {
+            status: Status {
+                world: byte(WORLD)
+                    .map(usize::from)
+                    .filter(|world| *world < NAMES.len())
+                    .unwrap_or(default.status.world),
+                on: byte(POWER).map_or(default.status.on, |on| on != 0),
+                brightness: byte(BRIGHTNESS).unwrap_or(default.status.brightness),
+            },
+            parameters,
+        }
+    }
+
+    /// Only what changed since the last save is written
+    pub fn save(&mut self, state: &State) -> Result<(), Error<F::Error>> {
+        let status = state.status;
+        self.store.set(WORLD, &[status.world as u8])?;
+        self.store.set(POWER, &[status.on as u8])?;
+        self.store.set(BRIGHTNESS, &[status.brightness])?;
+        for (index, parameter) in state.parameters.iter().enumerate() {
+            if let Some(parameter) = parameter {
+                self.store.set(
+                    PARAMETERS + index as u8,
+                    &[parameter.value, parameter.shade as u8],
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Proximity offset from the last calibration, `None` before the first one
+    pub fn proximity_offset(&self) -> Option<i8> {
+        match self.store.get(PROXIMITY_OFFSET) {
+            Some([offset]) => Some(*offset as i8),
+            _ => None,
+        }
+    }
+
+    pub fn set_proximity_offset(&mut self, offset: i8) -> Result<(), Error<F::Error>> {
+        self.store.set(PROXIMITY_OFFSET, &[offset as u8])
+    }
+
+    pub fn release(self) -> F {
+        self.store.release()
+    }
+}
@@ -0,0 +1,139 @@
+//! Flash in RAM for host tests and the simulator. Writes only clear bits
+//! like NOR flash, and the power can be cut in the middle of one.
+
+use embedded_storage::nor_flash::{
+    ErrorType, NorFlash, NorFlashError, NorFlashErrorKind, ReadNorFlash,
+};
+
+pub const SECTOR_SIZE: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    OutOfBounds,
+    NotAligned,
+    /// The power was cut, see `RamFlash::cut_after`
+    PowerLoss,
+}
+
+impl NorFlashError for Error {
+    fn kind(&self) -> NorFlashErrorKind {
+        match self {
+            Error::OutOfBounds => NorFlashErrorKind::OutOfBounds,
+            Error::NotAligned => NorFlashErrorKind::NotAligned,
+            Error::PowerLoss => NorFlashErrorKind::Other,
+        }
+    }
+}
+
+/// `SECTORS` sectors of `SECTOR_SIZE` bytes, erased
+pub struct RamFlash<const SECTORS: usize> {
+    sectors: [[u8; SECTOR_SIZE]; SECTORS],
+    erases: [usize; SECTORS],
+    /// Bytes written since created
+    written: usize,
+    /// Bytes written before the power goes
+    budget: Option<usize>,
+}
+
+impl<const SECTORS: usize> Default for RamFlash<SECTORS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SECTORS: usize> RamFlash<SECTORS> {
+    pub fn new() -> Self {
+        RamFlash {
+            sectors: [[0xFF; SECTOR_SIZE]; SECTORS],
+            erases: [0; SECTORS],
+            written: 0,
+            budget: None,
+        }
+    }
+
+    /// Times every sector was erased
+    pub fn erases(&self) -> &[usize; SECTORS] {
+        &self.erases
+    }
+
+    pub fn written(&self) -> usize {
+        self.written
+    }
+
+    /// Lose the power once `bytes` more bytes are written, erases included
+    /// as a single byte. `None` brings it back.
+    pub fn cut_after(&mut self, bytes: Option<usize>) {
+        self.budget = bytes;
+    }
+
+    fn spend(&mut self) -> Result<(), Error> {
+        match &mut self.budget {
+            Some(0) => Err(Error::PowerLoss),
+            Some(budget) => {
+                *budget -= 1;
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+impl<const SECTORS: usize> ErrorType for RamFlash<SECTORS> {
+    type Error = Error;
+}
+
+impl<const SECTORS: usize> ReadNorFlash for RamFlash<SECTORS> {
+    const READ_SIZE: usize = 1;
+
+    fn read(&mut self, offset: u32, bytes: &mut [u8]) -> Result<(), Error> {
+        let offset = offset as usize;
+        if offset + bytes.len() > self.capacity() {
+            return Err(Error::OutOfBounds);
+        }
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            let address = offset + i;
+            *byte = self.sectors[address / SECTOR_SIZE][address % SECTOR_SIZE];
+        }
+        Ok(())
+    }
+
+    fn capacity(&self) -> usize {
+        SECTORS * SECTOR_SIZE
+    }
+}
+
+impl<const SECTORS: usize> NorFlash for RamFlash<SECTORS> {
+    const WRITE_SIZE: usize = 1;
+    const ERASE_SIZE: usize = SECTOR_SIZE;
+
+    fn erase(&mut self, from: u32, to: u32) -> Result<(), Error> {
+        let (from, to) = (from as usize, to as usize);
+        if from % SECTOR_SIZE != 0 || to % SECTOR_SIZE != 0 {
+            return Err(Error::NotAligned);
+        }
+        if from > to || to > self.capacity() {
+            return Err(Error::OutOfBounds);
+        }
+        for sector in from / SECTOR_SIZE..to / SECTOR_SIZE {
+            self.spend()?;
+            self.sectors[sector] = [0xFF; SECTOR_SIZE];
+            self.erases[sector] += 1;
+        }
+        Ok(())
+    }
+
+    fn write(&mut self, offset: u32, bytes: &[u8]) -> Result<(), Error> {
+        let offset = offset as usize;
+        if offset + bytes.len() > self.capacity() {
+            return Err(Error::OutOfBounds);
+        }
+        for (i, byte) in bytes.iter().enumerate() {
+            self.spend()?;
+            let address = offset + i;
+            // Programming only clears bits
+            self.sectors[address / SECTOR_SIZE][address % SECTOR_SIZE] &= byte;
+            self.written += 1;
+        }
+        Ok(())
+    }
+}
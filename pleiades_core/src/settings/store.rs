@@ -0,0 +1,237 @@
+//! Key/value log over NOR flash. Records are appended to the current
+//! sector and the latest one of a key wins. When the sector is full the
+//! latest values move to the next sector, round-robin, so every sector
+//! wears the same. A sector only counts once its header is written, after
+//! the values it holds, so a power cut leaves the previous one in charge.
+//!
+//! Sector: `MAGIC`, sequence (u32 LE) and their CRC-8, then records of
+//! key, length, value and CRC-8, each padded to the write size. Erased flash reads
+//! `0xFF`, which ends the records.
+
+use embedded_storage::nor_flash::NorFlash;
+use heapless::{LinearMap, Vec};
+
+/// Largest value
+pub const VALUE_SIZE: usize = 8;
+/// Keys kept at once
+pub const KEYS: usize = 16;
+/// Left out, erased flash
+pub const ERASED: u8 = 0xFF;
+
+const MAGIC: [u8; 4] = *b"PLKV";
+const HEADER_SIZE: usize = 9;
+/// Key, length and CRC around the value
+const OVERHEAD: usize = 3;
+/// Largest record with its padding, for write sizes up to 4
+const RECORD_SIZE: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error<E> {
+    Flash(E),
+    /// `ERASED` as a key, or a value longer than `VALUE_SIZE`
+    Invalid,
+    /// More than `KEYS` keys
+    Full,
+}
+
+#[cfg(feature = "defmt")]
+impl<E> defmt::Format for Error<E> {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            Error::Flash(_) => defmt::write!(fmt, "Flash"),
+            Error::Invalid => defmt::write!(fmt, "Invalid"),
+            Error::Full => defmt::write!(fmt, "Full"),
+        }
+    }
+}
+
+pub struct Store<F: NorFlash> {
+    flash: F,
+    /// Of the first sector
+    start: u32,
+    sectors: u32,
+    /// Sector in charge with its sequence, `None` until the first write
+    current: Option<(u32, u32)>,
+    /// Of the next record, the end of the sector after a torn write
+    position: u32,
+    values: LinearMap<u8, Vec<u8, VALUE_SIZE>, KEYS>,
+}
+
+impl<F: NorFlash> Store<F> {
+    /// Load the latest values from the `sectors` erase blocks from `start`,
+    /// at least two of them. Needs a flash read byte by byte.
+    pub fn open(mut flash: F, start: u32, sectors: u32) -> Result<Self, Error<F::Error>> {
+        assert!(sectors >= 2 && F::READ_SIZE == 1 && F::WRITE_SIZE <= 4);
+
+        let mut current: Option<(u32, u32)> = None;
+        for sector in 0..sectors {
+            let mut header = [0u8; HEADER_SIZE];
+            flash
+                .read(start + sector * F::ERASE_SIZE as u32, &mut header)
+                .map_err(Error::Flash)?;
+            // Erased, something else, or cut before its end
+            if header[..4] != MAGIC || crc8(&header[..8]) != header[8] {
+                continue;
+            }
+            let sequence = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+            if !current.is_some_and(|(_, latest)| latest >= sequence) {
+                current = Some((sector, sequence));
+            }
+        }
+
+        let mut store = Store {
+            flash,
+            start,
+            sectors,
+            current,
+            position: 0,
+            values: LinearMap::new(),
+        };
+        if let Some((sector, _)) = current {
+            store.load(sector)?;
+        }
+        Ok(store)
+    }
+
+    /// Latest value of `key`
+    pub fn get(&self, key: u8) -> Option<&[u8]> {
+        self.values.get(&key).map(|value| value.as_slice())
+    }
+
+    /// Nothing is written when `key` already holds `value`
+    pub fn set(&mut self, key: u8, value: &[u8]) -> Result<(), Error<F::Error>> {
+        if key == ERASED || value.len() > VALUE_SIZE {
+            return Err(Error::Invalid);
+        }
+        if self.get(key) == Some(value) {
+            return Ok(());
+        }
+        let value = Vec::from_slice(value).map_err(|_| Error::Invalid)?;
+        if self.values.len() == KEYS && !self.values.contains_key(&key) {
+            return Err(Error::Full);
+        }
+
+        let size = Self::padded(OVERHEAD + value.len()) as u32;
+        if self.current.is_none() || self.position + size > self.end() {
+            self.compact()?;
+        }
+        self.position = self.append(self.position, key, &value)?;
+        let _ = self.values.insert(key, value);
+        Ok(())
+    }
+
+    pub fn release(self) -> F {
+        self.flash
+    }
+
+    fn load(&mut self, sector: u32) -> Result<(), Error<F::Error>> {
+        self.position = self.address(sector) + Self::padded(HEADER_SIZE) as u32;
+        let end = self.address(sector) + F::ERASE_SIZE as u32;
+
+        while self.position + OVERHEAD as u32 <= end {
+            let mut head = [0u8; 2];
+            self.flash
+                .read(self.position, &mut head)
+                .map_err(Error::Flash)?;
+            let [key, len] = head;
+            if key == ERASED {
+                return Ok(());
+            }
+
+            let len = len as usize;
+            let size = Self::padded(OVERHEAD + len) as u32;
+            let mut record = [0u8; RECORD_SIZE];
+            let torn = len > VALUE_SIZE || self.position + size > end || {
+                let record = &mut record[..OVERHEAD + len];
+                self.flash
+                    .read(self.position, record)
+                    .map_err(Error::Flash)?;
+                crc8(&record[..2 + len]) != record[2 + len]
+            };
+            if torn {
+                // Cut by a power loss, the next write moves on
+                self.position = end;
+                return Ok(());
+            }
+
+            let value = Vec::from_slice(&record[2..2 + len]).map_err(|_| Error::Invalid)?;
+            if self.values.insert(key, value).is_err() {
+                return Err(Error::Full);
+            }
+            self.position += size;
+        }
+        Ok(())
+    }
+
+    /// Latest values into the next sector, which then takes over
+    fn compact(&mut self) -> Result<(), Error<F::Error>> {
+        let (sector, sequence) = match self.current {
+            Some((sector, sequence)) => ((sector + 1) % self.sectors, sequence.wrapping_add(1)),
+            None => (0, 0),
+        };
+        let address = self.address(sector);
+        self.flash
+            .erase(address, address + F::ERASE_SIZE as u32)
+            .map_err(Error::Flash)?;
+
+        let mut position = address + Self::padded(HEADER_SIZE) as u32;
+        let values = core::mem::take(&mut self.values);
+        let copied = values.iter().try_fold(position, |position, (key, value)| {
+            self.append(position, *key, value)
+        });
+        self.values = values;
+        position = copied?;
+
+        let mut header = [ERASED; RECORD_SIZE];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4..8].copy_from_slice(&sequence.to_le_bytes());
+        header[8] = crc8(&header[..8]);
+        self.flash
+            .write(address, &header[..Self::padded(HEADER_SIZE)])
+            .map_err(Error::Flash)?;
+        self.current = Some((sector, sequence));
+        self.position = position;
+        Ok(())
+    }
+
+    /// Write a record at `position`, gives the position after it
+    fn append(&mut self, position: u32, key: u8, value: &[u8]) -> Result<u32, Error<F::Error>> {
+        let len = value.len();
+        let mut record = [ERASED; RECORD_SIZE];
+        record[0] = key;
+        record[1] = len as u8;
+        record[2..2 + len].copy_from_slice(value);
+        record[2 + len] = crc8(&record[..2 + len]);
+
+        let size = Self::padded(OVERHEAD + len);
+        self.flash
+            .write(position, &record[..size])
+            .map_err(Error::Flash)?;
+        Ok(position + size as u32)
+    }
+
+    fn address(&self, sector: u32) -> u32 {
+        self.start + sector * F::ERASE_SIZE as u32
+    }
+
+    /// Writes take whole multiples of the write size
+    fn padded(size: usize) -> usize {
+        size.next_multiple_of(F::WRITE_SIZE)
+    }
+
+    /// Of the sector in charge
+    fn end(&self) -> u32 {
+        let sector = self.current.map_or(0, |(sector, _)| sector);
+        self.address(sector) + F::ERASE_SIZE as u32
+    }
+}
+
+/// CRC-8 with the polynomial 0x07
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |crc, byte| {
+        (0..8).fold(crc ^ byte, |crc, _| match crc & 0x80 {
+            0 => crc << 1,
+            _ => (crc << 1) ^ 0x07,
+        })
+    })
+}
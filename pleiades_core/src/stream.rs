@@ -0,0 +1,183 @@
+//! Pixels streamed from lighting software such as xLights, as sACN (E1.31)
+//! or Art-Net DMX universes. A `Layout` maps the channels of consecutive
+//! universes onto the pixels of a `Frame`, three channels (R, G, B) per
+//! pixel, in the logical order of `world::stream::Stream`.
+//!
+//! Desktop tools may send whole frames instead, with the raw protocols
+//! DDP, TPM2.net or WLED's realtime packets. They go straight into the
+//! `Frame`, without a `Layout`.
+
+pub mod artnet;
+pub mod ddp;
+pub mod e131;
+#[cfg(feature = "net")]
+pub mod receiver;
+pub mod tpm2;
+pub mod wled;
+
+use core::ops::Range;
+use embassy_time::{Duration, Instant};
+use smart_leds::RGB8;
+
+/// Sources stop sending for this long before the worlds come back, the
+/// E1.31 network data loss timeout
+pub const TIMEOUT: Duration = Duration::from_millis(2500);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Shorter than its headers say
+    Truncated,
+    /// Not pixel data: another protocol, a poll, a sync or a preview
+    Unsupported,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Error {
+    fn format(&self, fmt: defmt::Formatter) {
+        match self {
+            Error::Truncated => defmt::write!(fmt, "Truncated"),
+            Error::Unsupported => defmt::write!(fmt, "Unsupported"),
+        }
+    }
+}
+
+/// Channel values of one universe, `data[0]` is channel 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dmx<'a> {
+    pub universe: u16,
+    pub data: &'a [u8],
+}
+
+/// Channels of a raw protocol, `data[0]` is the channel at `offset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Raw<'a> {
+    /// From 0, three channels per pixel
+    pub offset: usize,
+    pub data: &'a [u8],
+    /// Completes the frame, show it
+    pub last: bool,
+}
+
+/// E1.31 or Art-Net, told apart by their headers
+pub fn parse(packet: &[u8]) -> Result<Dmx<'_>, Error> {
+    match packet.starts_with(artnet::ID) {
+        true => artnet::parse(packet),
+        false => e131::parse(packet),
+    }
+}
+
+/// Where the pixels are in the universes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Layout {
+    /// Universe of the first pixel
+    pub universe: u16,
+    /// Channels used in every universe, 510 keeps 170 whole pixels in each
+    pub channels: u16,
+}
+
+impl Layout {
+    /// Universes holding the channels of `pixels` pixels
+    pub fn universes(&self, pixels: usize) -> Range<u16> {
+        let count = (pixels * 3).div_ceil(usize::from(self.channels.max(1)));
+        self.universe..self.universe.saturating_add(count as u16)
+    }
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        // xLights' defaults for E1.31
+        Self {
+            universe: 1,
+            channels: 510,
+        }
+    }
+}
+
+/// Pixels put together from the universes of a `Layout`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<const N: usize> {
+    pub pixels: [RGB8; N],
+}
+
+impl<const N: usize> Default for Frame<N> {
+    fn default() -> Self {
+        Self {
+            pixels: [RGB8::default(); N],
+        }
+    }
+}
+
+impl<const N: usize> Frame<N> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy the channels of `dmx` into the pixels they belong to, `false`
+    /// when the universe holds none of them
+    pub fn apply(&mut self, layout: &Layout, dmx: &Dmx) -> bool {
+        let Some(index) = dmx.universe.checked_sub(layout.universe) else {
+            return false;
+        };
+        let first = index as usize * layout.channels as usize;
+        if first >= N * 3 {
+            return false;
+        }
+
+        let data = &dmx.data[..dmx.data.len().min(layout.channels as usize)];
+        self.set(first, data);
+        true
+    }
+
+    /// Copy the channels of `raw` into the pixels, `true` when the frame
+    /// is complete
+    pub fn write(&mut self, raw: &Raw) -> bool {
+        self.set(raw.offset, raw.data);
+        raw.last
+    }
+
+    /// Channels past the last pixel are dropped
+    fn set(&mut self, first: usize, data: &[u8]) {
+        for (channel, value) in (first..N * 3).zip(data) {
+            let pixel = &mut self.pixels[channel / 3];
+            match channel % 3 {
+                0 => pixel.r = *value,
+                1 => pixel.g = *value,
+                _ => pixel.b = *value,
+            }
+        }
+    }
+}
+
+/// Tells when a stream starts and when it stopped for `TIMEOUT`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Watchdog {
+    until: Option<Instant>,
+}
+
+impl Watchdog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A frame came, `true` if it starts the stream
+    pub fn feed(&mut self, now: Instant) -> bool {
+        let started = self.until.is_none();
+        self.until = Some(now + TIMEOUT);
+        started
+    }
+
+    /// `true` once the stream stopped, until it starts again
+    pub fn expired(&mut self, now: Instant) -> bool {
+        match self.until {
+            Some(until) if now >= until => {
+                self.until = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.until.is_some()
+    }
+}
@@ -0,0 +1,30 @@
+//! Art-Net `ArtDmx` packets
+
+use super::{Dmx, Error};
+
+pub const PORT: u16 = 6454;
+pub const ID: &[u8; 8] = b"Art-Net\0";
+
+const OP_DMX: u16 = 0x5000;
+const HEADER: usize = 18;
+
+/// The universe is the 15-bit port-address, net, sub-net and universe
+pub fn parse(packet: &[u8]) -> Result<Dmx<'_>, Error> {
+    if packet.len() < HEADER {
+        return Err(Error::Truncated);
+    }
+    if &packet[..8] != ID {
+        return Err(Error::Unsupported);
+    }
+    // Polls, syncs and the rest
+    if u16::from_le_bytes([packet[8], packet[9]]) != OP_DMX {
+        return Err(Error::Unsupported);
+    }
+
+    let universe = u16::from_le_bytes([packet[14], packet[15] & 0x7F]);
+    let length = u16::from_be_bytes([packet[16], packet[17]]) as usize;
+    let data = packet
+        .get(HEADER..HEADER + length)
+        .ok_or(Error::Truncated)?;
+    Ok(Dmx { universe, data })
+}
@@ -0,0 +1,61 @@
+//! DDP (Distributed Display Protocol) data packets
+
+use super::{Error, Raw};
+
+pub const PORT: u16 = 4048;
+
+const VERSION_MASK: u8 = 0xC0;
+const VERSION_1: u8 = 0x40;
+const TIMECODE: u8 = 0x10;
+const REPLY: u8 = 0x04;
+const QUERY: u8 = 0x02;
+const PUSH: u8 = 0x01;
+/// Data type set by the application, not a pixel format
+const CUSTOM: u8 = 0x80;
+/// Pixel formats, undefined or RGB
+const UNDEFINED: u8 = 0;
+const RGB: u8 = 1;
+/// Bits per pixel element, undefined or 8
+const BITS_8: u8 = 3;
+/// Destination ids from here on take JSON control, config and status
+const CONTROL: u8 = 246;
+
+const HEADER: usize = 10;
+/// With the timecode after the length
+const HEADER_TIMECODE: usize = 14;
+
+/// The offset is in channels, a frame ends with the push flag
+pub fn parse(packet: &[u8]) -> Result<Raw<'_>, Error> {
+    if packet.len() < HEADER {
+        return Err(Error::Truncated);
+    }
+    let flags = packet[0];
+    if flags & VERSION_MASK != VERSION_1 || flags & (REPLY | QUERY) != 0 {
+        return Err(Error::Unsupported);
+    }
+    let kind = packet[2];
+    if kind & CUSTOM != 0
+        || !matches!((kind >> 3) & 0x07, UNDEFINED | RGB)
+        || !matches!(kind & 0x07, UNDEFINED | BITS_8)
+    {
+        return Err(Error::Unsupported);
+    }
+    if packet[3] >= CONTROL {
+        return Err(Error::Unsupported);
+    }
+
+    let offset = u32::from_be_bytes([packet[4], packet[5], packet[6], packet[7]]) as usize;
+    let length = u16::from_be_bytes([packet[8], packet[9]]) as usize;
+    let header = match flags & TIMECODE {
+        0 => HEADER,
+        _ => HEADER_TIMECODE,
+    };
+    let data = packet
+        .get(header..header + length)
+        .ok_or(Error::Truncated)?;
+    Ok(Raw {
+        offset,
+        data,
+        last: flags & PUSH != 0,
+    })
+}
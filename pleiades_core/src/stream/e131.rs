@@ -0,0 +1,58 @@
+//! sACN (ANSI E1.31) data packets
+
+use super::{Dmx, Error};
+
+pub const PORT: u16 = 5568;
+
+const PREAMBLE: [u8; 2] = [0x00, 0x10];
+const ID: &[u8; 12] = b"ASC-E1.17\0\0\0";
+const VECTOR_ROOT_DATA: [u8; 4] = [0, 0, 0, 0x04];
+const VECTOR_FRAMING_DATA: [u8; 4] = [0, 0, 0, 0x02];
+const VECTOR_DMP_SET_PROPERTY: u8 = 0x02;
+const ADDRESS_TYPE: u8 = 0xA1;
+/// Set in the framing options of data meant for a visualizer only
+const PREVIEW: u8 = 0x20;
+
+const OPTIONS: usize = 112;
+const UNIVERSE: usize = 113;
+const DMP_VECTOR: usize = 117;
+const DMP_ADDRESS_TYPE: usize = 118;
+const PROPERTY_COUNT: usize = 123;
+const START_CODE: usize = 125;
+/// First channel
+const DATA: usize = 126;
+
+/// Multicast group sources send `universe` to, 239.255.x.y
+pub fn multicast(universe: u16) -> [u8; 4] {
+    let [high, low] = universe.to_be_bytes();
+    [239, 255, high, low]
+}
+
+pub fn parse(packet: &[u8]) -> Result<Dmx<'_>, Error> {
+    if packet.len() < DATA {
+        return Err(Error::Truncated);
+    }
+    if packet[0..2] != PREAMBLE || &packet[4..16] != ID {
+        return Err(Error::Unsupported);
+    }
+    // Syncs and universe discovery have other vectors
+    if packet[18..22] != VECTOR_ROOT_DATA
+        || packet[40..44] != VECTOR_FRAMING_DATA
+        || packet[DMP_VECTOR] != VECTOR_DMP_SET_PROPERTY
+        || packet[DMP_ADDRESS_TYPE] != ADDRESS_TYPE
+    {
+        return Err(Error::Unsupported);
+    }
+    // Alternate start codes carry priorities and such, not levels
+    if packet[OPTIONS] & PREVIEW != 0 || packet[START_CODE] != 0 {
+        return Err(Error::Unsupported);
+    }
+
+    let universe = u16::from_be_bytes([packet[UNIVERSE], packet[UNIVERSE + 1]]);
+    // The start code counts as a property
+    let count = u16::from_be_bytes([packet[PROPERTY_COUNT], packet[PROPERTY_COUNT + 1]]) as usize;
+    let data = packet
+        .get(DATA..START_CODE + count.max(1))
+        .ok_or(Error::Truncated)?;
+    Ok(Dmx { universe, data })
+}
@@ -0,0 +1,78 @@
+//! Receives sACN, Art-Net, DDP, TPM2.net and WLED realtime packets over
+//! UDP on an embassy-net stack. sACN comes unicast or to the multicast
+//! groups of the universes, the others may be broadcast.
+
+use super::{artnet, ddp, e131, parse, tpm2, wled, Frame, Layout};
+use core::future::poll_fn;
+use core::task::Poll;
+use embassy_net::driver::Driver;
+use embassy_net::udp::{PacketMetadata, UdpSocket};
+use embassy_net::{Ipv4Address, Stack};
+use heapless::Vec;
+
+/// Largest packet, a whole frame of 256 pixels in one is 778 bytes
+const PACKET_SIZE: usize = 1472;
+/// Packets queued per socket
+const PACKETS: usize = 4;
+/// Queued bytes per socket, four E1.31 universes or two large frames
+const RX_SIZE: usize = 2 * PACKET_SIZE;
+/// One socket each
+const PORTS: [u16; 5] = [e131::PORT, artnet::PORT, ddp::PORT, tpm2::PORT, wled::PORT];
+
+/// Hand `show` the frame every time a universe of `layout` updates it, or
+/// a raw protocol completes it
+pub async fn receive<D: Driver, const N: usize>(
+    stack: &Stack<D>,
+    layout: &Layout,
+    mut show: impl FnMut(&Frame<N>),
+) -> ! {
+    let mut meta = [[PacketMetadata::EMPTY; PACKETS]; PORTS.len()];
+    let mut rx = [[0u8; RX_SIZE]; PORTS.len()];
+    let mut sockets: Vec<(u16, UdpSocket), { PORTS.len() }> = Vec::new();
+    for ((meta, rx), port) in meta.iter_mut().zip(rx.iter_mut()).zip(PORTS) {
+        // Nothing is sent back
+        let mut socket = UdpSocket::new(stack, meta, rx, &mut [], &mut []);
+        if let Err(err) = socket.bind(port) {
+            error!("Failed to bind stream port {}: {:?}", port, err);
+        }
+        let _ = sockets.push((port, socket));
+    }
+    for universe in layout.universes(N) {
+        let group = Ipv4Address(e131::multicast(universe));
+        if let Err(err) = stack.join_multicast_group(group).await {
+            error!("Failed to join sACN universe {}: {:?}", universe, err);
+        }
+    }
+
+    let mut frame = Frame::new();
+    let mut packet = [0u8; PACKET_SIZE];
+
+    loop {
+        let (port, received) = poll_fn(|cx| {
+            for (port, socket) in &sockets {
+                if let Poll::Ready(received) = socket.poll_recv_from(&mut packet, cx) {
+                    return Poll::Ready((*port, received));
+                }
+            }
+            Poll::Pending
+        })
+        .await;
+
+        match received {
+            Ok((len, _)) => {
+                let packet = &packet[..len];
+                // Not for us, or not pixels
+                let complete = match port {
+                    ddp::PORT => ddp::parse(packet).map(|raw| frame.write(&raw)),
+                    tpm2::PORT => tpm2::parse(packet).map(|raw| frame.write(&raw)),
+                    wled::PORT => wled::parse(packet).map(|raw| frame.write(&raw)),
+                    _ => parse(packet).map(|dmx| frame.apply(layout, &dmx)),
+                };
+                if complete == Ok(true) {
+                    show(&frame);
+                }
+            }
+            Err(err) => error!("Stream receive failed: {:?}", err),
+        }
+    }
+}
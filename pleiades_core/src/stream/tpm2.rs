@@ -0,0 +1,32 @@
+//! TPM2.net data packets
+
+use super::{Error, Raw};
+
+pub const PORT: u16 = 65506;
+
+const START: u8 = 0x9C;
+/// Commands and answers carry no pixels
+const DATA: u8 = 0xDA;
+
+const HEADER: usize = 6;
+
+/// Packets of a frame all hold as many channels as the first, numbered
+/// from 1. The end byte is not checked, some senders leave it out.
+pub fn parse(packet: &[u8]) -> Result<Raw<'_>, Error> {
+    if packet.len() < HEADER {
+        return Err(Error::Truncated);
+    }
+    if packet[0] != START || packet[1] != DATA {
+        return Err(Error::Unsupported);
+    }
+
+    let size = u16::from_be_bytes([packet[2], packet[3]]) as usize;
+    let number = packet[4];
+    let count = packet[5];
+    let data = packet.get(HEADER..HEADER + size).ok_or(Error::Truncated)?;
+    Ok(Raw {
+        offset: number.saturating_sub(1) as usize * size,
+        data,
+        last: number >= count,
+    })
+}
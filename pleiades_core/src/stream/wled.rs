@@ -0,0 +1,28 @@
+//! WLED's UDP realtime DRGB and DNRGB packets
+
+use super::{Error, Raw};
+
+/// Shared with WLED's sync notifications, which are left alone
+pub const PORT: u16 = 21324;
+
+/// RGB from the first pixel
+const DRGB: u8 = 2;
+/// RGB from the pixel in the header
+const DNRGB: u8 = 4;
+
+/// Every packet is shown. The timeout in the second byte is left to
+/// `TIMEOUT`.
+pub fn parse(packet: &[u8]) -> Result<Raw<'_>, Error> {
+    let (offset, data) = match packet {
+        [DRGB, _, data @ ..] => (0, data),
+        [DNRGB, _, high, low, data @ ..] => (u16::from_be_bytes([*high, *low]) as usize * 3, data),
+        [DRGB | DNRGB, ..] | [] => return Err(Error::Truncated),
+        // WARLS, DRGBW and the sync notifications
+        _ => return Err(Error::Unsupported),
+    };
+    Ok(Raw {
+        offset,
+        data,
+        last: true,
+    })
+}
@@ -0,0 +1,141 @@
+use crate::gesture::{Command, Direction};
+
+/// One reading of the four photodiodes, as stored in the gesture FIFO
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Dataset {
+    pub up: u8,
+    pub down: u8,
+    pub left: u8,
+    pub right: u8,
+}
+
+impl Dataset {
+    /// Balance of two opposite photodiodes, from -100 to 100
+    fn ratio(a: u8, b: u8) -> i32 {
+        match a as i32 + b as i32 {
+            0 => 0,
+            sum => (a as i32 - b as i32) * 100 / sum,
+        }
+    }
+
+    fn up_down(&self) -> i32 {
+        Self::ratio(self.up, self.down)
+    }
+
+    fn left_right(&self) -> i32 {
+        Self::ratio(self.left, self.right)
+    }
+
+    fn min(&self) -> u8 {
+        self.up.min(self.down).min(self.left).min(self.right)
+    }
+
+    fn max(&self) -> u8 {
+        self.up.max(self.down).max(self.left).max(self.right)
+    }
+}
+
+/// Tuning of the swipe recognizer, in gesture FIFO counts and datasets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwipeConfig {
+    /// Datasets with every photodiode at or below are ignored
+    pub floor: u8,
+    /// Change of balance between the first and the last dataset, beyond
+    /// which the hand has moved across the sensor
+    pub sensitivity: i32,
+    /// Datasets with every photodiode at or above mean a hand close to the sensor
+    pub near: u8,
+    /// Close datasets in a row before `Near` is reported
+    pub near_checks: u32,
+}
+
+impl Default for SwipeConfig {
+    fn default() -> Self {
+        Self {
+            floor: 10,
+            sensitivity: 50,
+            near: 200,
+            near_checks: 10,
+        }
+    }
+}
+
+/// Classifies the datasets of one gesture engine cycle, from the hand
+/// entering above the sensor to it leaving, into a swipe
+#[derive(Default)]
+pub struct SwipeRecognizer {
+    config: SwipeConfig,
+    first: Option<Dataset>,
+    last: Dataset,
+    near_checks: u32,
+    is_near: bool,
+    command: Option<Command>,
+}
+
+impl SwipeRecognizer {
+    pub fn new(config: SwipeConfig) -> Self {
+        Self {
+            config,
+            ..Default::default()
+        }
+    }
+
+    pub fn config(&self) -> SwipeConfig {
+        self.config
+    }
+
+    pub fn next(&mut self, dataset: Dataset) {
+        if dataset.max() <= self.config.floor {
+            return;
+        }
+        self.first.get_or_insert(dataset);
+        self.last = dataset;
+
+        // Hand hovering close, without moving across
+        match dataset.min() >= self.config.near {
+            true => self.near_checks += 1,
+            false => self.near_checks = 0,
+        }
+        // Once per gesture, `Far` follows when the hand leaves
+        if self.near_checks == self.config.near_checks && !self.is_near {
+            self.is_near = true;
+            self.command = Some(Command::Swipe(Direction::Near));
+        }
+    }
+
+    /// The gesture engine left gesture mode, the hand is gone
+    pub fn end(&mut self) {
+        if let Some(direction) = self.direction() {
+            self.command = Some(Command::Swipe(direction));
+        }
+        self.first = None;
+        self.near_checks = 0;
+        self.is_near = false;
+    }
+
+    fn direction(&self) -> Option<Direction> {
+        // The hand pulls away after a hold
+        if self.is_near {
+            return Some(Direction::Far);
+        }
+
+        let first = self.first?;
+        let up_down = self.last.up_down() - first.up_down();
+        let left_right = self.last.left_right() - first.left_right();
+        let sensitivity = self.config.sensitivity;
+
+        // The hand ends up over the photodiode it moved towards,
+        // the axis with the larger change wins
+        match up_down.abs() >= left_right.abs() {
+            true if up_down >= sensitivity => Some(Direction::Up),
+            true if up_down <= -sensitivity => Some(Direction::Down),
+            false if left_right >= sensitivity => Some(Direction::Left),
+            false if left_right <= -sensitivity => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    pub fn command(&mut self) -> Option<Command> {
+        self.command.take()
+    }
+}
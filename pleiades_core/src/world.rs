@@ -0,0 +1,288 @@
+use crate::{gesture::Direction, led_matrix::WritableMatrix, random::RandomSource};
+use pleiades_macro_derive::enum_world;
+
+pub mod diagnostic;
+pub mod empty;
+pub mod fire;
+pub mod matrix;
+pub mod northen_light;
+pub mod solid;
+pub mod starry_night;
+pub mod stream;
+pub mod utils;
+pub mod voronoi;
+
+pub trait Tick {
+    async fn tick(&mut self);
+}
+
+pub trait Flush {
+    async fn flush(&mut self);
+}
+
+pub trait OnDirection {
+    fn on_direction(&mut self, direction: Direction);
+
+    /// What `on_direction` changed, `None` for worlds without a parameter
+    fn parameter(&self) -> Option<Parameter> {
+        None
+    }
+
+    /// Start again from a `parameter` saved earlier
+    fn set_parameter(&mut self, _parameter: Parameter) {}
+}
+
+/// Parameter of a world, changed by the level gestures and saved across
+/// power cycles
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Parameter {
+    pub value: u8,
+    /// Change to the value of the colors, only the fire has one
+    pub shade: i8,
+}
+
+#[allow(clippy::large_enum_variant)]
+// The rotation, which also makes `NAMES` and `WORLDS`, then the others
+#[enum_world(
+    Fire,
+    NorthenLight,
+    Matrix,
+    Voronoi,
+    StarryNight,
+    Solid;
+    Empty,
+    Diagnostic,
+    Stream
+)]
+pub enum World<
+    'led,
+    Led: WritableMatrix,
+    Rng: RandomSource,
+    const C: usize,
+    const L: usize,
+    const N: usize,
+    const N2: usize,
+> {
+    Empty(empty::Empty<'led, Led>),
+    Fire(fire::Fire<'led, Led, Rng, C, L>),
+    NorthenLight(northen_light::NorthenLight<'led, Led, Rng, C, L, N>),
+    Matrix(matrix::Matrix<'led, Led, Rng, C, L, N, N2>),
+    Voronoi(voronoi::Voronoi<'led, Led, Rng, C, L, N>),
+    StarryNight(starry_night::StarryNight<'led, Led, Rng, C, L, N>),
+    Solid(solid::Solid<'led, Led, C, L, N>),
+    // Not in the rotation, shown when the gesture sensor is missing
+    Diagnostic(diagnostic::Diagnostic<'led, Led, C, L>),
+    // Not in the rotation, shown while pixels are streamed to the lantern
+    Stream(stream::Stream<'led, Led, C, L>),
+}
+
+pub struct Switch {
+    counter: usize,
+    prev_counter: usize,
+    // Of the worlds in `NAMES`, given to them every time they start
+    parameters: [Option<Parameter>; WORLDS],
+}
+
+impl Default for Switch {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Switch {
+    pub fn new() -> Self {
+        Switch {
+            counter: 1,
+            prev_counter: Default::default(),
+            parameters: [None; WORLDS],
+        }
+    }
+
+    /// Back on the world at `index` in `NAMES`, or off with that world
+    /// next, and with the parameters saved earlier
+    pub fn restore(index: usize, on: bool, parameters: [Option<Parameter>; WORLDS]) -> Self {
+        let counter = index.min(WORLDS - 1) + 1;
+        Switch {
+            counter: if on { counter } else { 0 },
+            prev_counter: counter,
+            parameters,
+        }
+    }
+
+    /// Parameters of the worlds in `NAMES`, `None` until changed
+    pub fn parameters(&self) -> [Option<Parameter>; WORLDS] {
+        self.parameters
+    }
+
+    /// Keep the parameter of `world` after a level gesture, for the next
+    /// time the world the switch is on starts
+    pub fn remember<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+        const N2: usize,
+    >(
+        &mut self,
+        world: &World<'led, Led, Rng, C, L, N, N2>,
+    ) {
+        // Not the diagnostic or the stream
+        if let (Some(index), Some(parameter)) = (self.counter.checked_sub(1), world.parameter()) {
+            self.parameters[index] = Some(parameter);
+        }
+    }
+
+    pub fn switch_world<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+        const N2: usize,
+    >(
+        &mut self,
+        led: &'led mut Led,
+        rng: &'led mut Rng,
+    ) -> World<'led, Led, Rng, C, L, N, N2> {
+        // Destroy old world and return peripherial resources
+        self.counter += 1;
+        self.counter = if self.counter > WORLDS {
+            1
+        } else {
+            self.counter
+        };
+        self.get_world(led, rng)
+    }
+
+    pub fn previous_world<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+        const N2: usize,
+    >(
+        &mut self,
+        led: &'led mut Led,
+        rng: &'led mut Rng,
+    ) -> World<'led, Led, Rng, C, L, N, N2> {
+        self.counter = if self.counter <= 1 {
+            WORLDS
+        } else {
+            self.counter - 1
+        };
+        self.get_world(led, rng)
+    }
+
+    /// Jump to the world at `index` in `NAMES`, switching the power on
+    pub fn select_world<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+        const N2: usize,
+    >(
+        &mut self,
+        index: usize,
+        led: &'led mut Led,
+        rng: &'led mut Rng,
+    ) -> World<'led, Led, Rng, C, L, N, N2> {
+        self.counter = index.min(WORLDS - 1) + 1;
+        self.get_world(led, rng)
+    }
+
+    /// Index in `NAMES` of the world shown, or shown again once the power
+    /// is back on
+    pub fn world(&self) -> usize {
+        match self.counter {
+            0 => self.prev_counter.max(1) - 1,
+            counter => counter - 1,
+        }
+    }
+
+    /// A world is shown, not the empty one of the power off
+    pub fn is_on(&self) -> bool {
+        self.counter != 0
+    }
+
+    /// Restart the world the switch is on
+    pub fn current_world<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+        const N2: usize,
+    >(
+        &mut self,
+        led: &'led mut Led,
+        rng: &'led mut Rng,
+    ) -> World<'led, Led, Rng, C, L, N, N2> {
+        self.get_world(led, rng)
+    }
+
+    /// Switch the power on or off whatever it is now, `true` when that
+    /// changed it and `current_world` has to start again
+    pub fn set_power(&mut self, on: bool) -> bool {
+        match (on, self.is_on()) {
+            (true, false) => self.counter = self.prev_counter,
+            (false, true) => {
+                self.prev_counter = self.counter;
+                self.counter = 0;
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    pub fn switch_power<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+        const N2: usize,
+    >(
+        &mut self,
+        led: &'led mut Led,
+        rng: &'led mut Rng,
+    ) -> World<'led, Led, Rng, C, L, N, N2> {
+        self.set_power(!self.is_on());
+        self.get_world(led, rng)
+    }
+
+    fn get_world<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+        const N2: usize,
+    >(
+        &mut self,
+        led: &'led mut Led,
+        rng: &'led mut Rng,
+    ) -> World<'led, Led, Rng, C, L, N, N2> {
+        let mut world = match self.counter {
+            0 => World::empty_new(led, rng),
+            counter => World::rotation_new(counter - 1, led, rng),
+        };
+        if let Some(parameter) = self
+            .counter
+            .checked_sub(1)
+            .and_then(|index| self.parameters[index])
+        {
+            world.set_parameter(parameter);
+        }
+        world
+    }
+}
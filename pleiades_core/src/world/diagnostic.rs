@@ -0,0 +1,67 @@
+use super::OnDirection;
+use crate::gesture::Direction;
+use crate::led_matrix::WritableMatrix;
+use crate::random::RandomSource;
+use crate::world::{Flush, Tick};
+use embassy_time::{Duration, Ticker};
+use pleiades_macro_derive::Flush;
+use smart_leds::RGB8;
+
+// Dim, a full white matrix draws more than the supply may give
+const LEVEL: u8 = 48;
+const BANDS: [RGB8; 4] = [
+    RGB8::new(LEVEL, 0, 0),
+    RGB8::new(0, LEVEL, 0),
+    RGB8::new(0, 0, LEVEL),
+    RGB8::new(LEVEL, LEVEL, LEVEL),
+];
+const BORDER: RGB8 = RGB8::new(LEVEL, 0, 0);
+// Ticks the border stays on, then off
+const BLINK: usize = 10;
+
+/// Shown when the gesture sensor does not answer: red, green, blue and
+/// white bands to check the LEDs, inside a blinking red border
+#[derive(Flush)]
+pub struct Diagnostic<'led, Led: WritableMatrix, const C: usize, const L: usize> {
+    led: &'led mut Led,
+    ticker: Ticker,
+    t: usize,
+}
+
+impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Diagnostic<'led, Led, C, L> {
+    pub fn new(led: &'led mut Led, _rng: &mut impl RandomSource) -> Self {
+        let ticker = Ticker::every(Duration::from_millis(50));
+
+        Diagnostic { led, ticker, t: 0 }
+    }
+}
+
+impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Tick
+    for Diagnostic<'led, Led, C, L>
+{
+    async fn tick(&mut self) {
+        let border = match (self.t / BLINK) % 2 {
+            0 => BORDER,
+            _ => RGB8::default(),
+        };
+
+        for x in 0..C {
+            for y in 0..L {
+                let color = match x == 0 || y == 0 || x == C - 1 || y == L - 1 {
+                    true => border,
+                    false => BANDS[y * BANDS.len() / L],
+                };
+                self.led.write(x, y, color);
+            }
+        }
+
+        self.t = self.t.wrapping_add(1);
+        self.ticker.next().await;
+    }
+}
+
+impl<'led, Led: WritableMatrix, const C: usize, const L: usize> OnDirection
+    for Diagnostic<'led, Led, C, L>
+{
+    fn on_direction(&mut self, _direction: Direction) {}
+}
@@ -1,6 +1,7 @@
 use super::OnDirection;
-use crate::apds9960::Direction;
+use crate::gesture::Direction;
 use crate::led_matrix::WritableMatrix;
+use crate::random::RandomSource;
 use crate::world::{Flush, Tick};
 use embassy_time::{Duration, Ticker};
 use pleiades_macro_derive::Flush;
@@ -12,7 +13,7 @@ pub struct Empty<'led, Led: WritableMatrix> {
 }
 
 impl<'led, Led: WritableMatrix> Empty<'led, Led> {
-    pub fn new(led: &'led mut Led) -> Self {
+    pub fn new(led: &'led mut Led, _rng: &mut impl RandomSource) -> Self {
         let ticker = Ticker::every(Duration::from_millis(50));
 
         Empty { led, ticker }
@@ -1,16 +1,15 @@
-use super::OnDirection;
-use crate::apds9960::Direction;
+use super::{OnDirection, Parameter};
 use crate::color::ColorGradient;
+use crate::gesture::Direction;
 use crate::led_matrix::WritableMatrix;
 use crate::perlin;
+use crate::random::RandomSource;
 use crate::world::utils::CooldownValue;
 use crate::world::{Flush, Tick};
 use core::cmp::max;
-use embassy_rp::clocks::RoscRng;
 use embassy_time::{Duration, Ticker};
 use heapless::Vec;
 use pleiades_macro_derive::Flush;
-use rand::Rng;
 use smart_leds::hsv::Hsv;
 use smart_leds::RGB8;
 
@@ -23,8 +22,9 @@ const MAX_SPARKS: usize = 2;
 const SPAWN_COOLDOWN: usize = 60;
 
 #[derive(Flush)]
-pub struct Fire<'led, Led: WritableMatrix, const C: usize, const L: usize> {
+pub struct Fire<'led, Led: WritableMatrix, Rng: RandomSource, const C: usize, const L: usize> {
     led: &'led mut Led,
+    rng: &'led mut Rng,
     noise: perlin::PerlinNoise,
     colormap: ColorGradient<COLORS>,
     height: CooldownValue<HEIGHT_COOLDOWN, HEIGHT_MIN, HEIGHT_MAX>,
@@ -34,10 +34,12 @@ pub struct Fire<'led, Led: WritableMatrix, const C: usize, const L: usize> {
     t: usize,
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Fire<'led, Led, C, L> {
-    pub fn new(led: &'led mut Led) -> Self {
-        let noise = perlin::PerlinNoise::new();
-        let colormap = Fire::<'led, Led, C, L>::get_colormap();
+impl<'led, Led: WritableMatrix, Rng: RandomSource, const C: usize, const L: usize>
+    Fire<'led, Led, Rng, C, L>
+{
+    pub fn new(led: &'led mut Led, rng: &'led mut Rng) -> Self {
+        let noise = perlin::PerlinNoise::new(rng);
+        let colormap = Fire::<'led, Led, Rng, C, L>::get_colormap();
         let height = CooldownValue::new(HEIGHT_INIT);
         let ticker = Ticker::every(Duration::from_millis(35));
         let sparks: Vec<Spark, MAX_SPARKS> = Vec::new();
@@ -45,6 +47,7 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Fire<'led, Led,
 
         Self {
             led,
+            rng,
             noise,
             colormap,
             height,
@@ -55,6 +58,7 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Fire<'led, Led,
         }
     }
 
+    #[allow(clippy::needless_range_loop)]
     fn antialiasing(&mut self) {
         let mut buffer = [[RGB8::default(); L]; C];
 
@@ -86,7 +90,9 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Fire<'led, Led,
     }
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Tick for Fire<'led, Led, C, L> {
+impl<'led, Led: WritableMatrix, Rng: RandomSource, const C: usize, const L: usize> Tick
+    for Fire<'led, Led, Rng, C, L>
+{
     async fn tick(&mut self) {
         self.led.clear();
 
@@ -109,7 +115,7 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Tick for Fire<'l
             // and write it to buffer
             for i in L - height..L {
                 let temp = (L - i - 1) as f32 / (height - 1) as f32;
-                let color = self.colormap.get_noised(temp, -0.2, 0.2);
+                let color = self.colormap.get_noised(temp, -0.2, 0.2, self.rng);
                 self.led.write(x, i, color);
             }
         }
@@ -122,11 +128,13 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Tick for Fire<'l
     }
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Fire<'led, Led, C, L> {
+impl<'led, Led: WritableMatrix, Rng: RandomSource, const C: usize, const L: usize>
+    Fire<'led, Led, Rng, C, L>
+{
     fn spawn_spark(&mut self, x: usize, height: usize) {
         self.spawn_counter += 1;
         if height < (C - 1)
-            && perlin::fair_rand_float() > 0.857
+            && self.rng.fair_rand_float() > 0.857
             && self.spawn_counter >= SPAWN_COOLDOWN
         {
             let spark = Spark {
@@ -140,17 +148,16 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Fire<'led, Led,
     }
 
     fn process_sparks(&mut self) {
-        self.sparks.iter_mut().for_each(|spark| spark.up());
+        self.sparks.iter_mut().for_each(|spark| spark.up(self.rng));
         self.sparks
             .retain(|spark| (spark.x >= 0) && (spark.x < C as isize) && (spark.y >= 0));
     }
 
     fn draw_sparks(&mut self) {
-        let mut rng = RoscRng;
-        let temp = rng.gen_range(0.8f32..=1.0);
+        let temp = self.rng.rand_float(0.8, 1.0);
 
         for spark in self.sparks.iter() {
-            let color = self.colormap.get_noised(temp, 0.0, 0.2);
+            let color = self.colormap.get_noised(temp, 0.0, 0.2, self.rng);
             self.led.write(spark.x as usize, spark.y as usize, color);
         }
     }
@@ -183,8 +190,8 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Fire<'led, Led,
     }
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize> OnDirection
-    for Fire<'led, Led, C, L>
+impl<'led, Led: WritableMatrix, Rng: RandomSource, const C: usize, const L: usize> OnDirection
+    for Fire<'led, Led, Rng, C, L>
 {
     fn on_direction(&mut self, direction: Direction) {
         match direction {
@@ -196,8 +203,22 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize> OnDirection
                 self.colormap.change_value(-20);
                 self.height.up();
             }
+            _ => {}
         }
     }
+
+    fn parameter(&self) -> Option<Parameter> {
+        Some(Parameter {
+            value: *self.height.value() as u8,
+            shade: self.colormap.diff(),
+        })
+    }
+
+    fn set_parameter(&mut self, parameter: Parameter) {
+        self.height.set(parameter.value as usize);
+        self.colormap
+            .change_value(parameter.shade.saturating_sub(self.colormap.diff()));
+    }
 }
 
 #[derive(Debug)]
@@ -207,8 +228,8 @@ struct Spark {
 }
 
 impl Spark {
-    fn up(&mut self) {
-        let rnd = perlin::fair_rand_float();
+    fn up(&mut self, rng: &mut impl RandomSource) {
+        let rnd = rng.fair_rand_float();
         let dir = match rnd {
             rnd if rnd <= 0.2 => -1,
             rnd if rnd >= 0.6 => 1,
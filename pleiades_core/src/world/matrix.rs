@@ -1,8 +1,8 @@
-use super::OnDirection;
-use crate::apds9960::Direction;
+use super::{OnDirection, Parameter};
 use crate::color::{Color, ColorGradient};
+use crate::gesture::Direction;
 use crate::led_matrix::WritableMatrix;
-use crate::perlin;
+use crate::random::RandomSource;
 use crate::world::utils::CooldownValue;
 use crate::world::{Flush, Tick};
 use core::marker::PhantomData;
@@ -19,12 +19,14 @@ const SPARKS_MAX_CHANCE: usize = 5;
 pub struct Matrix<
     'led,
     Led: WritableMatrix,
+    Rng: RandomSource,
     const C: usize,
     const L: usize,
     const N: usize,
     const N2: usize,
 > {
     led: &'led mut Led,
+    rng: &'led mut Rng,
     colormap: ColorGradient<C>,
     letters: Vec<Letters, N2>,
     ticker: Ticker,
@@ -36,13 +38,14 @@ pub struct Matrix<
 impl<
         'led,
         Led: WritableMatrix,
+        Rng: RandomSource,
         const C: usize,
         const L: usize,
         const N: usize,
         const N2: usize,
-    > Matrix<'led, Led, C, L, N, N2>
+    > Matrix<'led, Led, Rng, C, L, N, N2>
 {
-    pub fn new(led: &'led mut Led) -> Self {
+    pub fn new(led: &'led mut Led, rng: &'led mut Rng) -> Self {
         let ticker = Ticker::every(Duration::from_millis(30));
         let mut colormap = ColorGradient::new();
         let spawn_chance = CooldownValue::new(2);
@@ -55,6 +58,7 @@ impl<
 
         Self {
             led,
+            rng,
             colormap,
             letters,
             ticker,
@@ -68,11 +72,12 @@ impl<
 impl<
         'led,
         Led: WritableMatrix,
+        Rng: RandomSource,
         const C: usize,
         const L: usize,
         const N: usize,
         const N2: usize,
-    > Tick for Matrix<'led, Led, C, L, N, N2>
+    > Tick for Matrix<'led, Led, Rng, C, L, N, N2>
 {
     async fn tick(&mut self) {
         self.led.clear();
@@ -100,26 +105,27 @@ impl<
 impl<
         'led,
         Led: WritableMatrix,
+        Rng: RandomSource,
         const C: usize,
         const L: usize,
         const N: usize,
         const N2: usize,
-    > Matrix<'led, Led, C, L, N, N2>
+    > Matrix<'led, Led, Rng, C, L, N, N2>
 {
     fn spawn_letters(&mut self) {
-        let chance = perlin::rand_float(0.0, 1.0);
+        let chance = self.rng.rand_float(0.0, 1.0);
         let prob = 1.0 - *self.spawn_chance.value() as f32 / 10.0;
 
         if !self.letters.is_full() && chance >= prob {
             let x: usize = self.next_rnd_column();
 
-            let cool_rate = perlin::rand_float(0.005, 0.015);
-            let temperature = perlin::rand_float(0.8, 1.0);
-            let move_after = perlin::rand_uint(1, 12) as usize;
+            let cool_rate = self.rng.rand_float(0.005, 0.015);
+            let temperature = self.rng.rand_float(0.8, 1.0);
+            let move_after = self.rng.rand_uint(1, 12) as usize;
 
             let letter = Letters::new_falling(x, 0, move_after, temperature, cool_rate);
             if self.letters.push(letter).is_err() {
-                defmt::error!("Pushing letter in full vector while spawning.");
+                error!("Pushing letter in full vector while spawning.");
             }
         }
     }
@@ -129,11 +135,11 @@ impl<
 
         self.letters.iter_mut().for_each(|letter| match letter {
             Letters::Falling(ref mut l) => {
-                if l.down() {
+                if l.down(self.rng) {
                     let letter =
                         Letters::new_stationary(l.x, l.y - 1, l.temperature - 0.2, l.cool_rate);
                     if tmp_letters.push(letter).is_err() {
-                        defmt::error!("Pushing letter in full tmp vector.")
+                        error!("Pushing letter in full tmp vector.")
                     }
                 }
             }
@@ -146,7 +152,7 @@ impl<
         if N2 - self.letters.len() >= tmp_letters.len() {
             self.letters.extend(tmp_letters);
         } else {
-            defmt::error!(
+            error!(
                 "Pushing letter in full vector. Vector len: {}, new letters len: {}",
                 self.letters.len(),
                 tmp_letters.len()
@@ -164,7 +170,7 @@ impl<
     fn next_rnd_column(&mut self) -> usize {
         if self.rnd_col.is_empty() {
             self.rnd_col = (0..C).collect();
-            perlin::shuffle(&mut self.rnd_col);
+            self.rng.shuffle(&mut self.rnd_col);
         }
         self.rnd_col.remove(self.rnd_col.len() - 1)
     }
@@ -173,18 +179,31 @@ impl<
 impl<
         'led,
         Led: WritableMatrix,
+        Rng: RandomSource,
         const C: usize,
         const L: usize,
         const N: usize,
         const N2: usize,
-    > OnDirection for Matrix<'led, Led, C, L, N, N2>
+    > OnDirection for Matrix<'led, Led, Rng, C, L, N, N2>
 {
     fn on_direction(&mut self, direction: Direction) {
         match direction {
             Direction::Up => self.spawn_chance.up(),
             Direction::Down => self.spawn_chance.down(),
+            _ => {}
         }
     }
+
+    fn parameter(&self) -> Option<Parameter> {
+        Some(Parameter {
+            value: *self.spawn_chance.value() as u8,
+            ..Default::default()
+        })
+    }
+
+    fn set_parameter(&mut self, parameter: Parameter) {
+        self.spawn_chance.set(parameter.value as usize);
+    }
 }
 
 struct Falling;
@@ -239,12 +258,12 @@ struct Letter<LetterType> {
 }
 
 impl Letter<Falling> {
-    fn down(&mut self) -> bool {
+    fn down(&mut self, rng: &mut impl RandomSource) -> bool {
         match self.move_after == 0 {
             true => {
                 self.y += 1;
                 self.move_after = self.move_after_init;
-                self.temperature += perlin::rand_float(-0.2, 0.2);
+                self.temperature += rng.rand_float(-0.2, 0.2);
                 self.temperature = self.temperature.clamp(0.8, 1.0);
                 true
             }
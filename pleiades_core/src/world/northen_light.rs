@@ -1,14 +1,14 @@
-use super::OnDirection;
-use crate::apds9960::Direction;
+use super::{OnDirection, Parameter};
 use crate::color::{Color, ColorGradient};
+use crate::gesture::Direction;
 use crate::led_matrix::WritableMatrix;
 use crate::perlin;
+use crate::random::RandomSource;
 use crate::world::utils::CooldownValue;
 use crate::world::{Flush, Tick};
 use core::iter::Sum;
 use embassy_time::{Duration, Ticker};
 use heapless::Vec;
-use perlin::rand_float;
 use pleiades_macro_derive::Flush;
 use smart_leds::RGB8;
 
@@ -18,8 +18,16 @@ const PATTERNS_MIN: usize = 2;
 const PATTERNS_INIT: usize = 6;
 
 #[derive(Flush)]
-pub struct NorthenLight<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize> {
+pub struct NorthenLight<
+    'led,
+    Led: WritableMatrix,
+    Rng: RandomSource,
+    const C: usize,
+    const L: usize,
+    const N: usize,
+> {
     led: &'led mut Led,
+    rng: &'led mut Rng,
     colormap: ColorGradient<C>,
     ticker: Ticker,
     patterns: Vec<Pattern<L, C, N>, PATTERNS_MAX>,
@@ -28,17 +36,24 @@ pub struct NorthenLight<'led, Led: WritableMatrix, const C: usize, const L: usiz
     last_spawn: isize,
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
-    NorthenLight<'led, Led, C, L, N>
+impl<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+    > NorthenLight<'led, Led, Rng, C, L, N>
 {
-    pub fn new(led: &'led mut Led) -> Self {
+    pub fn new(led: &'led mut Led, rng: &'led mut Rng) -> Self {
         let ticker = Ticker::every(Duration::from_millis(20));
-        let colormap = NorthenLight::<'led, Led, C, L, N>::get_colormap();
+        let colormap = NorthenLight::<'led, Led, Rng, C, L, N>::get_colormap();
         let patterns: Vec<Pattern<L, C, N>, PATTERNS_MAX> = Vec::new();
         let curr_n_patterns = CooldownValue::new(PATTERNS_INIT);
 
         Self {
             led,
+            rng,
             colormap,
             ticker,
             patterns,
@@ -49,8 +64,14 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
     }
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize> Tick
-    for NorthenLight<'led, Led, C, L, N>
+impl<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+    > Tick for NorthenLight<'led, Led, Rng, C, L, N>
 {
     async fn tick(&mut self) {
         self.led.clear();
@@ -72,8 +93,14 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
     }
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
-    NorthenLight<'led, Led, C, L, N>
+impl<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+    > NorthenLight<'led, Led, Rng, C, L, N>
 {
     fn spawn_patterns(&mut self) {
         let time_till_last_spawn = self.t as isize - self.last_spawn;
@@ -81,9 +108,9 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
         let spawn_cooldown = 100 - *self.curr_n_patterns.value() as isize * 9;
 
         if !self.patterns.is_full() && !is_limit && time_till_last_spawn > spawn_cooldown {
-            let cutoff = rand_float(0.5, 0.55);
-            let lifetime = 250 + rand_float(-75.0, 75.0) as usize;
-            let pattern: Pattern<L, C, N> = Pattern::new(self.t, cutoff, lifetime);
+            let cutoff = self.rng.rand_float(0.5, 0.55);
+            let lifetime = 250 + self.rng.rand_float(-75.0, 75.0) as usize;
+            let pattern: Pattern<L, C, N> = Pattern::new(self.t, cutoff, lifetime, self.rng);
             self.patterns.push(pattern).unwrap();
             self.last_spawn = self.t as isize;
         }
@@ -112,8 +139,14 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
     }
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize> OnDirection
-    for NorthenLight<'led, Led, C, L, N>
+impl<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+    > OnDirection for NorthenLight<'led, Led, Rng, C, L, N>
 {
     fn on_direction(&mut self, direction: Direction) {
         match direction {
@@ -123,8 +156,20 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
             Direction::Down => {
                 self.curr_n_patterns.down();
             }
+            _ => {}
         }
     }
+
+    fn parameter(&self) -> Option<Parameter> {
+        Some(Parameter {
+            value: *self.curr_n_patterns.value() as u8,
+            ..Default::default()
+        })
+    }
+
+    fn set_parameter(&mut self, parameter: Parameter) {
+        self.curr_n_patterns.set(parameter.value as usize);
+    }
 }
 
 #[derive(Debug)]
@@ -135,9 +180,9 @@ struct Pattern<const L: usize, const C: usize, const N: usize> {
 }
 
 impl<const L: usize, const C: usize, const N: usize> Pattern<L, C, N> {
-    pub fn new(t: usize, cutoff: f32, lifetime: usize) -> Self {
-        let noise = perlin::PerlinNoise::new();
-        let data = Self::fill(noise, t, cutoff);
+    pub fn new(t: usize, cutoff: f32, lifetime: usize, rng: &mut impl RandomSource) -> Self {
+        let noise = perlin::PerlinNoise::new(rng);
+        let data = Self::fill(noise, t, cutoff, rng);
         Self {
             data,
             lifetime,
@@ -146,15 +191,20 @@ impl<const L: usize, const C: usize, const N: usize> Pattern<L, C, N> {
     }
 
     fn index(x: usize, y: usize) -> usize {
-        match x % 2 == 0 {
-            true => x * L + y,
-            false => x * L + (L - y) - 1,
+        match x % 2 {
+            0 => x * L + y,
+            _ => x * L + (L - y) - 1,
         }
     }
 
-    fn fill(noise: perlin::PerlinNoise, t: usize, cutoff: f32) -> [f32; N] {
+    fn fill(
+        noise: perlin::PerlinNoise,
+        t: usize,
+        cutoff: f32,
+        rng: &mut impl RandomSource,
+    ) -> [f32; N] {
         let mut data = [f32::default(); N];
-        let shift = rand_float(0.1, 0.8);
+        let shift = rng.rand_float(0.1, 0.8);
 
         for x in 0..C {
             for y in 0..L {
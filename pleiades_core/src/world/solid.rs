@@ -1,9 +1,9 @@
-use super::OnDirection;
-use crate::apds9960::Direction;
+use super::{OnDirection, Parameter};
 use crate::color::Color;
 use crate::color::ColorGradient;
+use crate::gesture::Direction;
 use crate::led_matrix::WritableMatrix;
-use crate::perlin;
+use crate::random::RandomSource;
 use crate::world::utils::CooldownValue;
 use crate::world::{Flush, Tick};
 use embassy_time::{Duration, Ticker};
@@ -26,9 +26,9 @@ pub struct Solid<'led, Led: WritableMatrix, const C: usize, const L: usize, cons
 impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
     Solid<'led, Led, C, L, N>
 {
-    pub fn new(led: &'led mut Led) -> Self {
+    pub fn new(led: &'led mut Led, rng: &mut impl RandomSource) -> Self {
         let colormap = Solid::<'led, Led, C, L, N>::get_colormap();
-        let init_hue = perlin::rand_uint(HUE_MIN as u32, HUE_MAX as u32) as usize;
+        let init_hue = rng.rand_uint(HUE_MIN as u32, HUE_MAX as u32) as usize;
         let hue = CooldownValue::new(init_hue);
         let ticker = Ticker::every(Duration::from_millis(50));
 
@@ -82,6 +82,18 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
             Direction::Down => {
                 self.hue.down();
             }
+            _ => {}
         }
     }
+
+    fn parameter(&self) -> Option<Parameter> {
+        Some(Parameter {
+            value: *self.hue.value() as u8,
+            ..Default::default()
+        })
+    }
+
+    fn set_parameter(&mut self, parameter: Parameter) {
+        self.hue.set(parameter.value as usize);
+    }
 }
@@ -1,6 +1,7 @@
-use super::OnDirection;
-use crate::apds9960::Direction;
+use super::{OnDirection, Parameter};
 use crate::color::{Color, ColorGradient};
+use crate::gesture::Direction;
+use crate::random::RandomSource;
 use crate::world::utils::CooldownValue;
 use crate::world::{Flush, Tick};
 use crate::{led_matrix::WritableMatrix, perlin};
@@ -20,8 +21,16 @@ const STAR_SPAWN_COOLDOWN: usize = 10;
 const Y_COOLDOWN: usize = 1;
 
 #[derive(Flush)]
-pub struct StarryNight<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize> {
+pub struct StarryNight<
+    'led,
+    Led: WritableMatrix,
+    Rng: RandomSource,
+    const C: usize,
+    const L: usize,
+    const N: usize,
+> {
     led: &'led mut Led,
+    rng: &'led mut Rng,
     stars_colormap: ColorGradient<STARS_COLORS>,
     stars: Vec<Star<C, L>, STARS>,
     ticker: Ticker,
@@ -33,13 +42,19 @@ pub struct StarryNight<'led, Led: WritableMatrix, const C: usize, const L: usize
     t: usize,
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
-    StarryNight<'led, Led, C, L, N>
+impl<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+    > StarryNight<'led, Led, Rng, C, L, N>
 {
-    pub fn new(led: &'led mut Led) -> Self {
+    pub fn new(led: &'led mut Led, rng: &'led mut Rng) -> Self {
         let ticker = Ticker::every(Duration::from_millis(50));
         let stars_colormap = Self::get_stars_colormap();
-        let buffer_space = Self::get_bg();
+        let buffer_space = Self::get_bg(rng);
         let buffer_new = buffer_space;
         let buffer_old = buffer_space;
 
@@ -49,11 +64,12 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
         let frames = CooldownValue::new(FRAMES_INIT);
 
         for _ in 0..INIT_STARS {
-            stars.push(Star::new()).unwrap();
+            stars.push(Star::new(rng)).unwrap();
         }
 
         Self {
             led,
+            rng,
             stars_colormap,
             ticker,
             stars,
@@ -90,23 +106,23 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
         stars_colormap
     }
 
-    fn get_bg() -> [[RGB8; L]; C] {
+    fn get_bg(rng: &mut Rng) -> [[RGB8; L]; C] {
         let mut buffer: [[smart_leds::RGB<u8>; L]; C] = [[RGB8::default(); L]; C];
         let mut bg_colormap: ColorGradient<3> = ColorGradient::new();
         bg_colormap.add_color(Color::new(0.0, RGB8::new(0, 0, 0)));
         bg_colormap.add_color(Color::new(0.8, RGB8::new(1, 2, 3)));
         bg_colormap.add_color(Color::new(1.01, RGB8::new(3, 1, 3)));
 
-        let noise = perlin::PerlinNoise::new();
-        let rnd = perlin::rand_uint(0, 100) as usize;
-        let shift = perlin::rand_float(0.1, 0.8);
+        let noise = perlin::PerlinNoise::new(rng);
+        let rnd = rng.rand_uint(0, 100) as usize;
+        let shift = rng.rand_float(0.1, 0.8);
 
         for (x, buffer) in buffer.iter_mut().enumerate().take(C) {
             for (y, buffer) in buffer.iter_mut().enumerate().take(L) {
                 let xx = (x.wrapping_add(rnd)) as f32 / 5.0;
                 let yy = (y.wrapping_add(rnd)) as f32 / 5.0;
 
-                let noise = noise.get2d([xx, yy]) as f32;
+                let noise = noise.get2d([xx, yy]);
                 let noise = noise - 0.45;
                 let noise = if noise <= 0.0 {
                     0.0
@@ -114,7 +130,7 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
                     (noise + shift).min(1.0)
                 };
 
-                *buffer = bg_colormap.get_noised(noise, -0.1, 0.1)
+                *buffer = bg_colormap.get_noised(noise, -0.1, 0.1, rng)
             }
         }
 
@@ -122,16 +138,23 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
     }
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize> Tick
-    for StarryNight<'led, Led, C, L, N>
+impl<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+    > Tick for StarryNight<'led, Led, Rng, C, L, N>
 {
     async fn tick(&mut self) {
-        if self.t % self.frames.value() == 0 {
+        let frame = self.t % self.frames.value();
+        if frame == 0 {
             self.buffer_old = self.buffer_new;
             self.buffer_new = self.step();
         }
 
-        let coef = (self.t % self.frames.value()) as f32 / (self.frames.value() - 1) as f32;
+        let coef = frame as f32 / (self.frames.value() - 1) as f32;
 
         for x in 0..C {
             for y in 0..L {
@@ -150,12 +173,18 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
     }
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
-    StarryNight<'led, Led, C, L, N>
+impl<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+    > StarryNight<'led, Led, Rng, C, L, N>
 {
     fn spawn_stars(&mut self) {
         if !self.stars.is_full() && self.since_star_spawn >= STAR_SPAWN_COOLDOWN {
-            self.stars.push(Star::new()).unwrap();
+            self.stars.push(Star::new(self.rng)).unwrap();
             self.since_star_spawn = 0;
         } else {
             self.since_star_spawn += 1;
@@ -170,27 +199,47 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
         self.stars.retain(|star| star.y() != 0);
     }
 
-    fn draw_stars(&self, buffer: &mut [[RGB8; L]; C]) {
+    fn draw_stars(&mut self, buffer: &mut [[RGB8; L]; C]) {
         self.stars.iter().for_each(|star| {
             buffer[star.x()][star.y()] =
-                self.stars_colormap.get_noised(star.temperature, -0.1, 0.1);
+                self.stars_colormap
+                    .get_noised(star.temperature, -0.1, 0.1, self.rng);
             if star.x() < C - 2 {
                 buffer[star.x() + 1][star.y()] =
-                    self.stars_colormap.get_noised(star.temperature, -0.1, 0.1);
+                    self.stars_colormap
+                        .get_noised(star.temperature, -0.1, 0.1, self.rng);
             }
         });
     }
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize> OnDirection
-    for StarryNight<'led, Led, C, L, N>
+impl<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+    > OnDirection for StarryNight<'led, Led, Rng, C, L, N>
 {
     fn on_direction(&mut self, direction: Direction) {
         match direction {
             Direction::Up => self.frames.down(),
             Direction::Down => self.frames.up(),
+            _ => {}
         }
     }
+
+    fn parameter(&self) -> Option<Parameter> {
+        Some(Parameter {
+            value: *self.frames.value() as u8,
+            ..Default::default()
+        })
+    }
+
+    fn set_parameter(&mut self, parameter: Parameter) {
+        self.frames.set(parameter.value as usize);
+    }
 }
 
 #[derive(Debug)]
@@ -202,12 +251,12 @@ struct Star<const C: usize, const L: usize> {
 }
 
 impl<const C: usize, const L: usize> Star<C, L> {
-    fn new() -> Self {
-        let x = perlin::rand_int(0, C as i32) as usize;
+    fn new(rng: &mut impl RandomSource) -> Self {
+        let x = rng.rand_int(0, C as i32) as usize;
         let y = L - 1;
         let since_y_moved = 0;
 
-        let temperature = perlin::fair_rand_float();
+        let temperature = rng.fair_rand_float();
 
         Star {
             x,
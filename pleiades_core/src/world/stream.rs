@@ -0,0 +1,49 @@
+use super::OnDirection;
+use crate::gesture::Direction;
+use crate::led_matrix::WritableMatrix;
+use crate::random::RandomSource;
+use crate::world::{Flush, Tick};
+use embassy_time::{Duration, Ticker};
+use pleiades_macro_derive::Flush;
+use smart_leds::RGB8;
+
+/// Frames a second shown of a stream, as told to the WLED apps
+pub const FPS: u8 = 60;
+
+/// Pixels streamed over the network, see `crate::stream`. Not in the
+/// rotation, the lantern comes here while a source is sending. Pixel `i`
+/// of a frame goes to `(i % C, i / C)`, row after row.
+#[derive(Flush)]
+pub struct Stream<'led, Led: WritableMatrix, const C: usize, const L: usize> {
+    led: &'led mut Led,
+    ticker: Ticker,
+}
+
+impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Stream<'led, Led, C, L> {
+    pub fn new(led: &'led mut Led, _rng: &mut impl RandomSource) -> Self {
+        // Black until the first frame
+        led.clear();
+        let ticker = Ticker::every(Duration::from_hz(FPS.into()));
+
+        Stream { led, ticker }
+    }
+
+    pub fn show(&mut self, pixels: &[RGB8]) {
+        for (i, color) in pixels.iter().take(C * L).enumerate() {
+            self.led.write(i % C, i / C, *color);
+        }
+    }
+}
+
+impl<'led, Led: WritableMatrix, const C: usize, const L: usize> Tick for Stream<'led, Led, C, L> {
+    async fn tick(&mut self) {
+        // The frames come through `show`, between the ticks
+        self.ticker.next().await;
+    }
+}
+
+impl<'led, Led: WritableMatrix, const C: usize, const L: usize> OnDirection
+    for Stream<'led, Led, C, L>
+{
+    fn on_direction(&mut self, _direction: Direction) {}
+}
@@ -39,4 +39,9 @@ impl<const COOLDOWNL: u8, const MIN: usize, const MAX: usize> CooldownValue<COOL
     pub fn value(&self) -> &usize {
         &self.value
     }
+
+    /// Back to a saved value, kept between `MIN` and `MAX`
+    pub fn set(&mut self, value: usize) {
+        self.value = value.clamp(MIN, MAX);
+    }
 }
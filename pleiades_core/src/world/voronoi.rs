@@ -1,8 +1,8 @@
-use super::OnDirection;
-use crate::apds9960::Direction;
+use super::{OnDirection, Parameter};
 use crate::color::{Color, ColorGradient};
+use crate::gesture::Direction;
 use crate::led_matrix::WritableMatrix;
-use crate::perlin;
+use crate::random::RandomSource;
 use crate::world::utils::CooldownValue;
 use crate::world::{Flush, Tick};
 use core::f32::consts::PI;
@@ -19,8 +19,16 @@ const POINTS_MAX: usize = 20;
 const TIMES_OF_DAY: usize = 3;
 
 #[derive(Flush)]
-pub struct Voronoi<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize> {
+pub struct Voronoi<
+    'led,
+    Led: WritableMatrix,
+    Rng: RandomSource,
+    const C: usize,
+    const L: usize,
+    const N: usize,
+> {
     led: &'led mut Led,
+    rng: &'led mut Rng,
     buffer_new: [[RGB8; L]; C],
     buffer_old: [[RGB8; L]; C],
     model: Model<L, C>,
@@ -29,18 +37,25 @@ pub struct Voronoi<'led, Led: WritableMatrix, const C: usize, const L: usize, co
     time: f32,
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
-    Voronoi<'led, Led, C, L, N>
+impl<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+    > Voronoi<'led, Led, Rng, C, L, N>
 {
-    pub fn new(led: &'led mut Led) -> Self {
+    pub fn new(led: &'led mut Led, rng: &'led mut Rng) -> Self {
         let ticker = Ticker::every(Duration::from_millis(20));
         let time = PI / 2.0;
         let mut model: Model<L, C> = Model::new();
-        let buffer_new = model.step(time);
+        let buffer_new = model.step(time, rng);
         let buffer_old = buffer_new;
 
         Self {
             led,
+            rng,
             model,
             ticker,
             buffer_new,
@@ -51,8 +66,14 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
     }
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize> Tick
-    for Voronoi<'led, Led, C, L, N>
+impl<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+    > Tick for Voronoi<'led, Led, Rng, C, L, N>
 {
     async fn tick(&mut self) {
         self.led.clear();
@@ -60,7 +81,7 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
         self.time += 1e-3;
         if self.t == 0 {
             self.buffer_old = self.buffer_new;
-            self.buffer_new = self.model.step(self.time);
+            self.buffer_new = self.model.step(self.time, self.rng);
         }
 
         let r = self.t as f32 / 10.0;
@@ -83,8 +104,14 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
     }
 }
 
-impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize> OnDirection
-    for Voronoi<'led, Led, C, L, N>
+impl<
+        'led,
+        Led: WritableMatrix,
+        Rng: RandomSource,
+        const C: usize,
+        const L: usize,
+        const N: usize,
+    > OnDirection for Voronoi<'led, Led, Rng, C, L, N>
 {
     fn on_direction(&mut self, direction: Direction) {
         match direction {
@@ -94,8 +121,22 @@ impl<'led, Led: WritableMatrix, const C: usize, const L: usize, const N: usize>
             Direction::Down => {
                 self.model.desired_points_count.down();
             }
+            _ => {}
         }
     }
+
+    fn parameter(&self) -> Option<Parameter> {
+        Some(Parameter {
+            value: *self.model.desired_points_count.value() as u8,
+            ..Default::default()
+        })
+    }
+
+    fn set_parameter(&mut self, parameter: Parameter) {
+        self.model
+            .desired_points_count
+            .set(parameter.value as usize);
+    }
 }
 
 struct Point<const L: usize, const C: usize> {
@@ -106,12 +147,12 @@ struct Point<const L: usize, const C: usize> {
 }
 
 impl<const L: usize, const C: usize> Point<L, C> {
-    fn new() -> Self {
+    fn new(rng: &mut impl RandomSource) -> Self {
         Point {
-            x: perlin::rand_uint(0, C as u32) as isize,
-            y: perlin::rand_uint(0, L as u32) as isize,
-            x_shift: perlin::rand_int(-1, 2) as isize,
-            y_shift: perlin::rand_int(-1, 2) as isize,
+            x: rng.rand_uint(0, C as u32) as isize,
+            y: rng.rand_uint(0, L as u32) as isize,
+            x_shift: rng.rand_int(-1, 2) as isize,
+            y_shift: rng.rand_int(-1, 2) as isize,
         }
     }
 
@@ -130,9 +171,9 @@ impl<const L: usize, const C: usize> Point<L, C> {
         }
     }
 
-    fn change_dir(&mut self) {
-        self.x_shift = perlin::rand_int(-1, 2) as isize;
-        self.y_shift = perlin::rand_int(-1, 2) as isize;
+    fn change_dir(&mut self, rng: &mut impl RandomSource) {
+        self.x_shift = rng.rand_int(-1, 2) as isize;
+        self.y_shift = rng.rand_int(-1, 2) as isize;
     }
 }
 
@@ -183,28 +224,28 @@ impl<const L: usize, const C: usize> Model<L, C> {
         }
     }
 
-    fn spawn_and_kill_points(&mut self) {
+    fn spawn_and_kill_points(&mut self, rng: &mut impl RandomSource) {
         while self.points.len() != *self.desired_points_count.value() {
             match self.points.len() > *self.desired_points_count.value() {
                 true => {
                     self.points.pop();
                 }
                 false => {
-                    let point: Point<L, C> = Point::<L, C>::new();
+                    let point: Point<L, C> = Point::<L, C>::new(rng);
                     if self.points.push(point).is_err() {
-                        defmt::error!("Overflow while trying to spawn a new point.")
+                        error!("Overflow while trying to spawn a new point.")
                     }
                 }
             }
         }
     }
 
-    fn step(&mut self, time: f32) -> [[RGB8; L]; C] {
-        self.spawn_and_kill_points();
+    fn step(&mut self, time: f32, rng: &mut impl RandomSource) -> [[RGB8; L]; C] {
+        self.spawn_and_kill_points(rng);
 
         let mut index_matrix = [[0usize; L]; C];
         let mut buffer = [[RGB8::default(); L]; C];
-        let sin = (time.sin() + 1.0) / 2.0; // [0..1]
+        let sin = (F32Ext::sin(time) + 1.0) / 2.0; // [0..1]
 
         for (x, index_matrix) in index_matrix.iter_mut().enumerate().take(C) {
             for (y, index_matrix) in index_matrix.iter_mut().enumerate().take(L) {
@@ -258,8 +299,8 @@ impl<const L: usize, const C: usize> Model<L, C> {
         }
 
         self.points.iter_mut().for_each(|p| {
-            if perlin::rand_float(0.0, 1.0) > 0.6 {
-                p.change_dir();
+            if rng.rand_float(0.0, 1.0) > 0.6 {
+                p.change_dir(rng);
             }
             p.go();
         });
@@ -0,0 +1,46 @@
+use pleiades_core::als::{AlsConfig, AlsGain, Light};
+
+fn close(actual: f32, expected: f32) -> bool {
+    (actual - expected).abs() < 0.5
+}
+
+#[test]
+fn atime() {
+    let config = |cycles| AlsConfig {
+        cycles,
+        gain: AlsGain::X1,
+    };
+    assert_eq!(config(1).atime(), 0xFF);
+    assert_eq!(config(72).atime(), 0xB8);
+    assert_eq!(config(256).atime(), 0x00);
+    assert_eq!(config(10).max_count(), 10250);
+    assert_eq!(config(256).max_count(), u16::MAX);
+}
+
+#[test]
+fn lux_and_cct() {
+    let light = Light::new(1000, 400, 350, 300, &AlsConfig::default());
+    assert!(close(light.lux, 130.7), "{light:?}");
+    assert!(close(light.cct, 4185.0), "{light:?}");
+
+    // Four times the gain, four times the counts for the same light
+    let config = AlsConfig {
+        gain: AlsGain::X16,
+        ..Default::default()
+    };
+    let light = Light::new(4000, 1600, 1400, 1200, &config);
+    assert!(close(light.lux, 130.7), "{light:?}");
+}
+
+#[test]
+fn saturated_and_dark() {
+    let config = AlsConfig {
+        cycles: 1,
+        ..Default::default()
+    };
+    let light = Light::new(1025, 900, 900, 900, &config);
+    assert_eq!((light.lux, light.cct), (0.0, 0.0));
+
+    let light = Light::new(0, 0, 0, 0, &AlsConfig::default());
+    assert_eq!((light.lux, light.cct), (0.0, 0.0));
+}
@@ -0,0 +1,199 @@
+//! Register-level tests of the driver against a mock I2C bus, which fails
+//! on any transaction that differs from the expected ones.
+
+use embassy_futures::block_on;
+use embedded_hal_async::i2c::ErrorKind;
+use embedded_hal_mock::eh1::i2c::{Mock, Transaction};
+use pleiades_core::apds9960::{Apds9960, Error, DEV_ADDR};
+use pleiades_core::gesture::{Command, Direction, GestureConfig};
+use pleiades_core::proximity::{
+    ConfigError, LedBoost, LedDrive, ProximityConfig, ProximityGain, WaitTime,
+};
+
+const ENABLE: u8 = 0x80;
+const ID: u8 = 0x92;
+const PILT: u8 = 0x89;
+const PIHT: u8 = 0x8B;
+const WTIME: u8 = 0x83;
+const PERS: u8 = 0x8C;
+const CONFIG1: u8 = 0x8D;
+const PPULSE: u8 = 0x8E;
+const CONTROL: u8 = 0x8F;
+const CONFIG2: u8 = 0x90;
+const STATUS: u8 = 0x93;
+const PDATA: u8 = 0x9C;
+const POFFSET_UR: u8 = 0x9D;
+const POFFSET_DL: u8 = 0x9E;
+const GPENTH: u8 = 0xA0;
+const GPEXTH: u8 = 0xA1;
+const GCONFIG1: u8 = 0xA2;
+const GCONFIG2: u8 = 0xA3;
+const GCONFIG4: u8 = 0xAB;
+const GFLVL: u8 = 0xAE;
+const GFIFO_U: u8 = 0xFC;
+
+fn mock(expectations: &[Transaction]) -> Apds9960<Mock> {
+    Apds9960::new(Mock::new(expectations), GestureConfig::default())
+}
+
+/// Every expected transaction took place
+fn done(apds: Apds9960<Mock>) {
+    apds.release().done();
+}
+
+#[test]
+fn enable() {
+    let mut apds = mock(&[Transaction::write(DEV_ADDR, vec![ENABLE, 0b0000_0101])]);
+    block_on(apds.enable()).unwrap();
+    done(apds);
+}
+
+#[test]
+fn powerup() {
+    let mut apds = mock(&[
+        // Keeps the ALS gain
+        Transaction::write_read(DEV_ADDR, vec![CONTROL], vec![0b0000_0001]),
+        Transaction::write(DEV_ADDR, vec![CONTROL, 0b0000_0001]),
+        Transaction::write(DEV_ADDR, vec![CONFIG2, 0b0011_0001]),
+        Transaction::write(DEV_ADDR, vec![PPULSE, 0b0100_0000]),
+        Transaction::write_read(DEV_ADDR, vec![ENABLE], vec![0b0000_1101]),
+        Transaction::write(DEV_ADDR, vec![ENABLE, 0b0000_0101]),
+    ]);
+    block_on(apds.powerup()).unwrap();
+    done(apds);
+}
+
+#[test]
+fn proximity_config() {
+    let config = ProximityConfig {
+        gain: ProximityGain::X4,
+        led_drive: LedDrive::Ma25,
+        led_boost: LedBoost::X1_5,
+        pulses: 8,
+        wait: Some(WaitTime {
+            cycles: 10,
+            long: true,
+        }),
+        ..Default::default()
+    };
+    let mut apds = mock(&[
+        Transaction::write_read(DEV_ADDR, vec![CONTROL], vec![0]),
+        Transaction::write(DEV_ADDR, vec![CONTROL, 0b1000_1000]),
+        Transaction::write(DEV_ADDR, vec![CONFIG2, 0b0001_0001]),
+        Transaction::write(DEV_ADDR, vec![PPULSE, 0b0100_0111]),
+        Transaction::write(DEV_ADDR, vec![WTIME, 246]),
+        Transaction::write(DEV_ADDR, vec![CONFIG1, 0b0110_0010]),
+        Transaction::write_read(DEV_ADDR, vec![ENABLE], vec![0b0000_0101]),
+        Transaction::write(DEV_ADDR, vec![ENABLE, 0b0000_1101]),
+    ]);
+    block_on(apds.set_proximity_config(config)).unwrap();
+    assert_eq!(apds.proximity_config(), config);
+    done(apds);
+
+    // Rejected before anything reaches the bus
+    let invalid = ProximityConfig {
+        pulses: 65,
+        ..Default::default()
+    };
+    let mut apds = mock(&[]);
+    assert_eq!(
+        block_on(apds.set_proximity_config(invalid)),
+        Err(Error::Config(ConfigError::Pulses(65)))
+    );
+    assert_eq!(apds.proximity_config(), ProximityConfig::default());
+    done(apds);
+}
+
+#[test]
+fn read() {
+    let mut apds = mock(&[
+        Transaction::write_read(DEV_ADDR, vec![STATUS], vec![0b0000_0010]),
+        Transaction::write_read(DEV_ADDR, vec![PDATA], vec![42]),
+    ]);
+    assert_eq!(block_on(apds.read()), Ok(42));
+    assert_eq!(apds.proximity(), 42);
+    done(apds);
+}
+
+#[test]
+fn read_not_ready() {
+    let mut apds = mock(&[Transaction::write_read(
+        DEV_ADDR,
+        vec![STATUS],
+        vec![0b0000_0001],
+    )]);
+    assert_eq!(block_on(apds.read()), Err(Error::NotReady));
+    done(apds);
+}
+
+#[test]
+fn proximity_interrupt() {
+    let mut apds = mock(&[
+        Transaction::write(DEV_ADDR, vec![PILT, 0]),
+        Transaction::write(DEV_ADDR, vec![PIHT, 3]),
+        Transaction::write(DEV_ADDR, vec![PERS, 0b0001_0000]),
+        // Keeps PON and PEN, adds PIEN
+        Transaction::write_read(DEV_ADDR, vec![ENABLE], vec![0b0000_0101]),
+        Transaction::write(DEV_ADDR, vec![ENABLE, 0b0010_0101]),
+    ]);
+    block_on(apds.enable_proximity_interrupt()).unwrap();
+    done(apds);
+}
+
+#[test]
+fn proximity_offset() {
+    let mut apds = mock(&[
+        Transaction::write(DEV_ADDR, vec![POFFSET_UR, 0b1000_0101]),
+        Transaction::write(DEV_ADDR, vec![POFFSET_DL, 0b1000_0101]),
+    ]);
+    block_on(apds.set_proximity_offset(-5)).unwrap();
+    done(apds);
+}
+
+#[test]
+fn bus_error() {
+    let mut apds = mock(&[
+        Transaction::write_read(DEV_ADDR, vec![STATUS], vec![0]).with_error(ErrorKind::Other)
+    ]);
+    assert_eq!(block_on(apds.read()), Err(Error::Bus(ErrorKind::Other)));
+    done(apds);
+}
+
+#[test]
+fn check_id() {
+    let mut apds = mock(&[
+        Transaction::write_read(DEV_ADDR, vec![ID], vec![0xAB]),
+        Transaction::write_read(DEV_ADDR, vec![ID], vec![0x39]),
+    ]);
+    assert_eq!(block_on(apds.check_id()), Ok(()));
+    assert_eq!(block_on(apds.check_id()), Err(Error::WrongId(0x39)));
+    done(apds);
+}
+
+#[test]
+fn gesture_engine() {
+    let mut apds = mock(&[
+        Transaction::write(DEV_ADDR, vec![GPENTH, 40]),
+        Transaction::write(DEV_ADDR, vec![GPEXTH, 30]),
+        Transaction::write(DEV_ADDR, vec![GCONFIG1, 0b0100_0001]),
+        Transaction::write(DEV_ADDR, vec![GCONFIG2, 0b0100_0001]),
+        Transaction::write(DEV_ADDR, vec![GCONFIG4, 0]),
+        Transaction::write_read(DEV_ADDR, vec![ENABLE], vec![0b0000_0101]),
+        Transaction::write(DEV_ADDR, vec![ENABLE, 0b0100_0101]),
+        // A hand held close, ten datasets of the FIFO
+        Transaction::write_read(DEV_ADDR, vec![GFLVL], vec![10]),
+        Transaction::write_read(DEV_ADDR, vec![GFIFO_U], vec![230; 40]),
+        // Gone, out of gesture mode
+        Transaction::write_read(DEV_ADDR, vec![GFLVL], vec![0]),
+        Transaction::write_read(DEV_ADDR, vec![GCONFIG4], vec![0]),
+    ]);
+    block_on(apds.enable_gesture_engine()).unwrap();
+
+    // Only the FIFO is read, never PDATA
+    block_on(apds.gesture()).unwrap();
+    assert_eq!(apds.command(), Some(Command::Swipe(Direction::Near)));
+    block_on(apds.gesture()).unwrap();
+    assert_eq!(apds.command(), Some(Command::Swipe(Direction::Far)));
+    assert!(apds.is_idle());
+    done(apds);
+}
@@ -0,0 +1,99 @@
+use pleiades_core::api::{handle, Method, ParseError, Request, Status};
+use pleiades_core::gesture::{Command, Direction};
+
+const STATUS: Status = Status {
+    world: 0,
+    on: true,
+    brightness: 255,
+};
+
+fn request(method: &str, path: &str, body: &str) -> String {
+    format!(
+        "{method} {path} HTTP/1.1\r\nHost: pleiades\r\ncontent-length: {}\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+fn call(method: &str, path: &str, body: &str) -> (u16, String, Option<Command>) {
+    let raw = request(method, path, body);
+    let request = Request::parse(raw.as_bytes()).expect("Invalid request");
+    let (response, command) = handle(&request, STATUS);
+    (response.status, response.body.to_string(), command)
+}
+
+#[test]
+fn parse() {
+    let raw = request("PUT", "/api/power?now", "{\"on\":false}");
+    assert_eq!(
+        Request::parse(raw.as_bytes()),
+        Ok(Request {
+            method: Method::Put,
+            path: "/api/power",
+            body: b"{\"on\":false}",
+        })
+    );
+
+    // Body still on its way
+    assert_eq!(
+        Request::parse(&raw.as_bytes()[..raw.len() - 1]),
+        Err(ParseError::Incomplete)
+    );
+    assert_eq!(
+        Request::parse(b"GET /api/status"),
+        Err(ParseError::Incomplete)
+    );
+    assert_eq!(Request::parse(b"hello\r\n\r\n"), Err(ParseError::Invalid));
+}
+
+#[test]
+fn status() {
+    assert_eq!(
+        call("GET", "/api/status", ""),
+        (
+            200,
+            r#"{"world":"fire","on":true,"brightness":255}"#.into(),
+            None
+        )
+    );
+
+    let (status, body, _) = call("GET", "/api/worlds", "");
+    assert_eq!(status, 200);
+    assert!(
+        body.starts_with(r#"{"worlds":["fire","northen_light","#),
+        "{body}"
+    );
+    assert!(body.ends_with(r#""solid"],"current":"fire"}"#), "{body}");
+}
+
+#[test]
+fn commands() {
+    let (status, body, command) = call("PUT", "/api/world", r#"{"world":"matrix"}"#);
+    assert_eq!((status, command), (200, Some(Command::World(2))));
+    assert!(body.contains(r#""world":"matrix""#), "{body}");
+
+    let (_, _, command) = call("POST", "/api/parameter", r#"{"direction":"down"}"#);
+    assert_eq!(command, Some(Command::Level(Direction::Down)));
+
+    let (_, body, command) = call("PUT", "/api/brightness", r#"{"brightness":64}"#);
+    assert_eq!(command, Some(Command::Brightness(64)));
+    assert!(body.contains(r#""brightness":64"#), "{body}");
+
+    // Sets the power whatever the status says
+    let (_, _, command) = call("PUT", "/api/power", r#"{"on":false}"#);
+    assert_eq!(command, Some(Command::Power(false)));
+    let (_, _, command) = call("PUT", "/api/power", r#"{"on":true}"#);
+    assert_eq!(command, Some(Command::Power(true)));
+}
+
+#[test]
+fn errors() {
+    assert_eq!(call("PUT", "/api/world", r#"{"world":"sun"}"#).0, 404);
+    assert_eq!(
+        call("PUT", "/api/brightness", r#"{"brightness":300}"#).0,
+        400
+    );
+    assert_eq!(call("PUT", "/api/power", "on").0, 400);
+    assert_eq!(call("DELETE", "/api/status", "").0, 405);
+    assert_eq!(call("GET", "/index.html", "").0, 404);
+    assert_eq!(call("GET", "/api/world", "").2, None);
+}
@@ -0,0 +1,36 @@
+use pleiades_core::calibration::{self, Calibration, MAX_OFFSET};
+
+/// Search against a lantern whose baseline drops by one count per two
+/// offset steps
+fn run(idle: u8, target: u8) -> i8 {
+    let mut search = Calibration::new(target);
+    let mut steps = 0;
+    while let Some(offset) = search.offset() {
+        search.measured(idle.saturating_sub(offset as u8 / 2));
+        steps += 1;
+    }
+    assert!(steps <= 8, "{steps} measurements");
+    search.result()
+}
+
+#[test]
+fn search() {
+    assert_eq!(run(0, 1), 0);
+    assert_eq!(run(40, 1), 78);
+    // Nothing brings it down, settle for the strongest offset
+    assert_eq!(run(200, 1), MAX_OFFSET as i8);
+}
+
+#[test]
+fn poffset() {
+    assert_eq!(calibration::poffset(0), 0x00);
+    assert_eq!(calibration::poffset(42), 0x2A);
+    assert_eq!(calibration::poffset(-42), 0xAA);
+    assert_eq!(calibration::poffset(i8::MIN), 0xFF);
+}
+
+#[test]
+fn baseline() {
+    assert_eq!(calibration::baseline(&mut [5, 4, 250, 5, 6]), 5);
+    assert_eq!(calibration::baseline(&mut []), 0);
+}
@@ -0,0 +1,262 @@
+use embassy_futures::block_on;
+use pleiades_core::api::{Lantern, Status};
+use pleiades_core::console::{
+    self, Console, GestureSetting, LineBuffer, ParseError, ProximitySetting, Request,
+};
+use pleiades_core::gesture::{Command, Direction, GestureConfig};
+use pleiades_core::proximity::{LedDrive, ProximityConfig, ProximityGain, WaitTime};
+use std::cell::{Cell, RefCell};
+
+/// Records the commands it is sent
+struct Fake {
+    status: Status,
+    proximity: Option<u8>,
+    sent: RefCell<Vec<Command>>,
+    gesture: Cell<GestureConfig>,
+    proximity_config: Cell<ProximityConfig>,
+}
+
+impl Lantern for Fake {
+    fn status(&self) -> Status {
+        self.status
+    }
+
+    async fn send(&self, command: Command) {
+        self.sent.borrow_mut().push(command);
+    }
+}
+
+impl Console for Fake {
+    fn proximity(&self) -> Option<u8> {
+        self.proximity
+    }
+
+    fn gesture_config(&self) -> GestureConfig {
+        self.gesture.get()
+    }
+
+    async fn set_gesture_config(&self, config: GestureConfig) {
+        self.gesture.set(config);
+    }
+
+    fn proximity_config(&self) -> ProximityConfig {
+        self.proximity_config.get()
+    }
+
+    async fn set_proximity_config(&self, config: ProximityConfig) {
+        self.proximity_config.set(config);
+    }
+}
+
+fn fake(proximity: Option<u8>) -> Fake {
+    Fake {
+        status: Status {
+            world: 1,
+            on: true,
+            brightness: 128,
+        },
+        proximity,
+        sent: RefCell::default(),
+        gesture: Cell::default(),
+        proximity_config: Cell::default(),
+    }
+}
+
+fn run(fake: &Fake, line: &str) -> String {
+    let mut out = String::new();
+    block_on(console::dispatch(line, fake, &mut out)).unwrap();
+    out
+}
+
+#[test]
+fn parse() {
+    let command = |command| Ok(Request::Command(command));
+    assert_eq!(console::parse("world voronoi"), command(Command::World(3)));
+    assert_eq!(
+        console::parse("world next"),
+        command(Command::Swipe(Direction::Right))
+    );
+    assert_eq!(
+        console::parse("  up "),
+        command(Command::Level(Direction::Up))
+    );
+    assert_eq!(
+        console::parse("down"),
+        command(Command::Level(Direction::Down))
+    );
+    assert_eq!(
+        console::parse("brightness 12"),
+        command(Command::Brightness(12))
+    );
+    assert_eq!(console::parse("power"), command(Command::SwitchPower));
+    assert_eq!(console::parse("power off"), command(Command::Power(false)));
+    assert_eq!(console::parse("sensor raw"), Ok(Request::SensorRaw));
+    assert_eq!(console::parse(""), Ok(Request::Empty));
+
+    assert_eq!(
+        console::parse("world sun"),
+        Err(ParseError::UnknownWorld("sun"))
+    );
+    assert_eq!(
+        console::parse("brightness 256"),
+        Err(ParseError::Usage("brightness <0-255>"))
+    );
+    assert_eq!(console::parse("up 2"), Err(ParseError::Unknown("up 2")));
+    assert_eq!(console::parse("dance"), Err(ParseError::Unknown("dance")));
+}
+
+#[test]
+fn dispatch() {
+    let lantern = fake(Some(7));
+    assert_eq!(run(&lantern, "world fire"), "ok\r\n");
+    assert_eq!(run(&lantern, "power on"), "ok\r\n");
+    assert_eq!(run(&lantern, "power off"), "ok\r\n");
+    assert_eq!(
+        *lantern.sent.borrow(),
+        [
+            Command::World(0),
+            Command::Power(true),
+            Command::Power(false)
+        ]
+    );
+
+    assert_eq!(
+        run(&lantern, "status"),
+        "world northen_light, on, brightness 128\r\n"
+    );
+    assert_eq!(run(&lantern, "sensor raw"), "pdata 7\r\n");
+    assert_eq!(run(&fake(None), "sensor raw"), "sensor missing\r\n");
+    assert_eq!(
+        run(&lantern, "dance"),
+        "unknown command dance, try help\r\n"
+    );
+    assert!(run(&lantern, "help").contains("sensor raw"));
+    assert_eq!(run(&lantern, ""), "");
+}
+
+#[test]
+fn gesture() {
+    assert_eq!(console::parse("gesture"), Ok(Request::Gesture(None)));
+    assert_eq!(
+        console::parse("gesture near 150"),
+        Ok(Request::Gesture(Some(GestureSetting::Near(150))))
+    );
+    assert_eq!(
+        console::parse("gesture level_threshold -2"),
+        Ok(Request::Gesture(Some(GestureSetting::LevelThreshold(-2))))
+    );
+    for line in ["gesture near", "gesture near 300", "gesture reach 3"] {
+        assert!(
+            matches!(console::parse(line), Err(ParseError::Usage(_))),
+            "{line}"
+        );
+    }
+    assert_eq!(
+        console::parse("gesture near 150 2"),
+        Err(ParseError::Unknown("gesture near 150 2"))
+    );
+    assert_eq!(console::parse("up 2 3"), Err(ParseError::Unknown("up 2 3")));
+
+    // One field changes, the others stay
+    let lantern = fake(None);
+    assert_eq!(run(&lantern, "gesture near 150"), "ok\r\n");
+    assert_eq!(run(&lantern, "gesture floor 5"), "ok\r\n");
+    assert_eq!(
+        lantern.gesture.get(),
+        GestureConfig {
+            near: 150,
+            floor: 5,
+            ..GestureConfig::default()
+        }
+    );
+    assert_eq!(
+        run(&lantern, "gesture"),
+        "floor 5, entry_checks 8, swing_window 30, near 150, power_checks 20, \
+         calibrate_checks 300, level_checks 6, level_threshold 1\r\n"
+    );
+}
+
+#[test]
+fn proximity() {
+    let setting = |line| match console::parse(line) {
+        Ok(Request::Proximity(Some(setting))) => Some(setting),
+        _ => None,
+    };
+    assert_eq!(console::parse("proximity"), Ok(Request::Proximity(None)));
+    assert_eq!(
+        setting("proximity gain 4"),
+        Some(ProximitySetting::Gain(ProximityGain::X4))
+    );
+    assert_eq!(
+        setting("proximity led_drive 12.5"),
+        Some(ProximitySetting::LedDrive(LedDrive::Ma12_5))
+    );
+    assert_eq!(
+        setting("proximity wait_long 10"),
+        Some(ProximitySetting::Wait(Some(WaitTime {
+            cycles: 10,
+            long: true
+        })))
+    );
+    assert_eq!(
+        setting("proximity wait off"),
+        Some(ProximitySetting::Wait(None))
+    );
+    // Out of the datasheet's range
+    for line in [
+        "proximity gain 3",
+        "proximity pulses 0",
+        "proximity pulses 65",
+        "proximity wait 257",
+        "proximity pulses",
+    ] {
+        assert!(
+            matches!(console::parse(line), Err(ParseError::Usage(_))),
+            "{line}"
+        );
+    }
+
+    let lantern = fake(None);
+    assert_eq!(
+        run(&lantern, "proximity"),
+        "gain 1, led_drive 100, led_boost 3, pulse_length 8, pulses 1, wait off\r\n"
+    );
+    assert_eq!(run(&lantern, "proximity pulses 8"), "ok\r\n");
+    assert_eq!(run(&lantern, "proximity wait 20"), "ok\r\n");
+    assert_eq!(
+        lantern.proximity_config.get(),
+        ProximityConfig {
+            pulses: 8,
+            wait: Some(WaitTime {
+                cycles: 20,
+                long: false
+            }),
+            ..ProximityConfig::default()
+        }
+    );
+    assert_eq!(
+        run(&lantern, "proximity"),
+        "gain 1, led_drive 100, led_boost 3, pulse_length 8, pulses 8, wait 20\r\n"
+    );
+}
+
+#[test]
+fn line_buffer() {
+    let mut buffer: LineBuffer<8> = LineBuffer::new();
+    let lines = Cell::new(Vec::new());
+    let mut feed = |bytes: &[u8]| {
+        for byte in bytes {
+            if let Some(line) = buffer.push(*byte) {
+                let mut all = lines.take();
+                all.push(line.to_string());
+                lines.set(all);
+            }
+        }
+    };
+
+    feed(b"up\r\n");
+    feed(b"dowx\x7fn\n");
+    // Too long, the rest is dropped
+    feed(b"brightness 3\r");
+    assert_eq!(lines.take(), ["up", "down", "brightne"]);
+}
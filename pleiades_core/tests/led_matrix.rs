@@ -0,0 +1,137 @@
+use embassy_futures::block_on;
+use pleiades_core::led_matrix::{
+    Correction, LedMatrix, Lut, PioWrite, PowerBudget, WritableMatrix, CANDLE, TYPICAL_SMD5050,
+};
+use smart_leds::RGB8;
+use std::sync::atomic::AtomicU8;
+
+const N: usize = 256;
+
+/// Keeps the last frame written
+struct Strip {
+    last: [RGB8; N],
+}
+
+impl PioWrite<N> for Strip {
+    async fn write(&mut self, colors: &[RGB8; N]) {
+        self.last = *colors;
+    }
+}
+
+const WHITE: RGB8 = RGB8::new(255, 255, 255);
+
+#[test]
+fn current() {
+    let budget = PowerBudget::default();
+    // 60 mA an LED at full white, 1 mA when off
+    assert_eq!(budget.current(&[WHITE; N]), 61 * N as u32);
+    assert_eq!(budget.current(&[RGB8::default(); N]), N as u32);
+    assert_eq!(budget.current(&[RGB8::new(255, 0, 0); 4]), 4 * 21);
+}
+
+#[test]
+fn cap() {
+    let budget = PowerBudget::default();
+    // Within the limit
+    let dim = [RGB8::new(10, 0, 0); N];
+    assert_eq!(budget.cap(&dim, u8::MAX), u8::MAX);
+    assert_eq!(budget.cap(&[RGB8::default(); N], 100), 100);
+
+    // Full white is dimmed under the limit, a lower brightness too
+    let white = [WHITE; N];
+    let capped = budget.cap(&white, u8::MAX);
+    assert!(capped < 20, "{capped}");
+    assert_eq!(budget.cap(&white, 200), capped);
+    assert_eq!(budget.cap(&white, 5), 5);
+
+    // Not even the idle draw fits
+    let tight = PowerBudget {
+        limit: 100,
+        ..budget
+    };
+    assert_eq!(tight.cap(&white, u8::MAX), 0);
+}
+
+#[test]
+fn flush() {
+    let mut strip = Strip {
+        last: [RGB8::default(); N],
+    };
+    let brightness = AtomicU8::new(u8::MAX);
+    let budget = PowerBudget::default();
+    let mut matrix: LedMatrix<Strip, 16, N> = LedMatrix::with_brightness(&mut strip, &brightness);
+    matrix.set_power_budget(budget);
+
+    matrix.bg(WHITE);
+    block_on(matrix.flush());
+    // Under the limit, by less than the rounding of the brightness
+    let current = budget.current(&strip.last);
+    assert!(current <= budget.limit, "{current}");
+    assert!(current > budget.limit * 4 / 5, "{current}");
+    assert!(strip.last.iter().all(|color| *color == strip.last[0]));
+
+    // A frame within the limit is left alone
+    let mut matrix: LedMatrix<Strip, 16, N> = LedMatrix::with_brightness(&mut strip, &brightness);
+    matrix.set_power_budget(budget);
+    matrix.write(3, 4, WHITE);
+    block_on(matrix.flush());
+    assert_eq!(
+        strip.last.iter().filter(|color| **color == WHITE).count(),
+        1
+    );
+}
+
+#[test]
+fn lut() {
+    // Nothing to correct
+    let lut = Lut::new(&Correction::default());
+    for level in 0..=255 {
+        let color = RGB8::new(level, level, level);
+        assert_eq!(lut.apply(color), color);
+    }
+
+    let gamma = Lut::new(&Correction {
+        gamma: 2.2,
+        ..Correction::default()
+    });
+    let level = |level| gamma.apply(RGB8::new(level, 0, 0)).r;
+    assert_eq!((level(0), level(255)), (0, 255));
+    assert!((50..60).contains(&level(128)), "{}", level(128));
+    // Dim tones stay lit
+    assert_eq!(level(1), 1);
+    assert!((1..=255).all(|l| level(l) >= level(l - 1)));
+
+    // Balance and temperature scale the channels
+    let warm = Lut::new(&Correction {
+        gamma: 1.0,
+        color: TYPICAL_SMD5050,
+        temperature: CANDLE,
+    });
+    assert_eq!(warm.apply(WHITE), RGB8::new(255, 101, 39));
+    let off = Lut::new(&Correction {
+        temperature: RGB8::new(255, 255, 0),
+        ..Correction::default()
+    });
+    assert_eq!(off.apply(RGB8::new(1, 1, 1)), RGB8::new(1, 1, 0));
+}
+
+#[test]
+fn correction() {
+    let mut strip = Strip {
+        last: [RGB8::default(); N],
+    };
+    let brightness = AtomicU8::new(127);
+    let correction = Correction {
+        gamma: 2.2,
+        ..Correction::default()
+    };
+    let mut matrix: LedMatrix<Strip, 16, N> = LedMatrix::with_brightness(&mut strip, &brightness);
+    matrix.set_correction(&correction);
+    matrix.write(0, 0, WHITE);
+    block_on(matrix.flush());
+
+    // Dimmed to half, then corrected
+    let lut = Lut::new(&correction);
+    assert_eq!(strip.last[0], lut.apply(RGB8::new(127, 127, 127)));
+    assert_eq!(strip.last[1], RGB8::default());
+}
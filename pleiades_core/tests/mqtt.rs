@@ -0,0 +1,404 @@
+use embassy_futures::block_on;
+use embedded_io_async::{ErrorKind, ErrorType, Read, Write};
+use pleiades_core::api::{Lantern, Status};
+use pleiades_core::gesture::{Command, Direction};
+use pleiades_core::mqtt::client::{self, Client, Gestures, PACKET_SIZE};
+use pleiades_core::mqtt::{self, discovery, Config, Connect, Error, Message, Packet};
+use pleiades_core::world::NAMES;
+use std::future::poll_fn;
+use std::io::{self, Read as _, Write as _};
+use std::net::TcpStream;
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+const STATUS: Status = Status {
+    world: 0,
+    on: true,
+    brightness: 255,
+};
+
+fn message<'a>(topic: &'a str, payload: &'a str) -> Message<'a> {
+    Message {
+        topic,
+        payload: payload.as_bytes(),
+        retain: false,
+    }
+}
+
+#[test]
+fn connect() {
+    let mut buffer = [0u8; 64];
+    let connect = Connect {
+        client_id: "lamp",
+        keep_alive: 60,
+        will: Some(Message {
+            topic: "p/a",
+            payload: b"offline",
+            retain: true,
+        }),
+        username: Some("u"),
+        password: Some("pw"),
+    };
+    let len = mqtt::connect(&mut buffer, &connect).unwrap();
+
+    #[rustfmt::skip]
+    let expected = [
+        0x10, 37,
+        0, 4, b'M', b'Q', b'T', b'T', 4,
+        // Username, password, will retain, will, clean session
+        0xE6, 0, 60,
+        0, 4, b'l', b'a', b'm', b'p',
+        0, 3, b'p', b'/', b'a',
+        0, 7, b'o', b'f', b'f', b'l', b'i', b'n', b'e',
+        0, 1, b'u',
+        0, 2, b'p', b'w',
+    ];
+    assert_eq!(&buffer[..len], &expected);
+    assert_eq!(
+        mqtt::connect(&mut buffer[..20], &connect),
+        Err(Error::Overflow)
+    );
+}
+
+#[test]
+fn packets() {
+    let mut buffer = [0u8; 512];
+
+    // Retained, and long enough for two bytes of remaining length
+    let payload = [b'x'; 200];
+    let published = Message {
+        topic: "pleiades/world",
+        payload: &payload,
+        retain: true,
+    };
+    let len = mqtt::publish(&mut buffer, &published).unwrap();
+    assert_eq!(&buffer[..3], &[0x31, 216, 1]);
+    assert_eq!(mqtt::frame(&buffer[..len - 1]), Ok(None));
+    assert_eq!(mqtt::frame(&buffer[..len + 5]), Ok(Some(len)));
+    assert_eq!(mqtt::decode(&buffer[..len]), Ok(Packet::Publish(published)));
+
+    let len = mqtt::subscribe(&mut buffer, 7, &["a/b", "c"]).unwrap();
+    assert_eq!(
+        &buffer[..len],
+        &[0x82, 12, 0, 7, 0, 3, b'a', b'/', b'b', 0, 0, 1, b'c', 0]
+    );
+
+    // QoS 1 carries a packet id before the payload
+    let qos1 = [0x32, 7, 0, 1, b't', 0, 9, b'o', b'k'];
+    assert_eq!(mqtt::decode(&qos1), Ok(Packet::Publish(message("t", "ok"))));
+    assert_eq!(
+        mqtt::decode(&[0x20, 2, 0, 5]),
+        Ok(Packet::ConnAck {
+            session_present: false,
+            code: 5
+        })
+    );
+    assert_eq!(
+        mqtt::decode(&[0x90, 3, 0, 7, 0]),
+        Ok(Packet::SubAck { id: 7, codes: &[0] })
+    );
+    assert_eq!(mqtt::decode(&[0xD0, 0]), Ok(Packet::PingResp));
+    assert_eq!(
+        mqtt::frame(&[0x30, 0xFF, 0xFF, 0xFF, 0xFF]),
+        Err(Error::Malformed)
+    );
+}
+
+#[test]
+fn commands() {
+    let config = Config {
+        base: "home/lantern",
+        ..Config::default()
+    };
+    let command = |topic, payload| config.command(&message(topic, payload));
+    let off = Status {
+        on: false,
+        ..STATUS
+    };
+
+    assert_eq!(
+        command("home/lantern/world/set", "matrix"),
+        Some(Command::World(2))
+    );
+    assert_eq!(
+        command("home/lantern/world/set", "next"),
+        Some(Command::Swipe(Direction::Right))
+    );
+    assert_eq!(command("home/lantern/world/set", "sunrise"), None);
+    assert_eq!(
+        command("home/lantern/brightness/set", "128"),
+        Some(Command::Brightness(128))
+    );
+    assert_eq!(command("home/lantern/brightness/set", "300"), None);
+    // Set whatever the lantern shows, only TOGGLE depends on it
+    assert_eq!(
+        command("home/lantern/power/set", "ON"),
+        Some(Command::Power(true))
+    );
+    assert_eq!(
+        command("home/lantern/power/set", "OFF"),
+        Some(Command::Power(false))
+    );
+    assert_eq!(
+        command("home/lantern/power/set", "TOGGLE"),
+        Some(Command::SwitchPower)
+    );
+    // Not our lantern, or a state topic
+    assert_eq!(command("home/lantern2/power/set", "OFF"), None);
+    assert_eq!(command("home/lantern/power", "OFF"), None);
+
+    assert_eq!(mqtt::world(STATUS), "fire");
+    assert_eq!(mqtt::power(off), "OFF");
+    assert_eq!(mqtt::brightness(STATUS), "255");
+    assert_eq!(
+        mqtt::gesture(Command::Swipe(Direction::Left)),
+        Some("swipe_left")
+    );
+    assert_eq!(
+        mqtt::gesture(Command::Swipe(Direction::Near)),
+        Some("swipe_near")
+    );
+    assert_eq!(mqtt::gesture(Command::Brightness(3)), None);
+}
+
+#[test]
+fn discovery() {
+    let config = Config {
+        client_id: "lantern-1",
+        base: "home/lantern",
+        ..Config::default()
+    };
+    assert_eq!(
+        discovery::topic(&config).unwrap().unwrap(),
+        "homeassistant/light/lantern-1/config"
+    );
+    assert_eq!(
+        discovery::topic(&Config {
+            discovery: None,
+            ..config
+        }),
+        Ok(None)
+    );
+
+    let payload = discovery::payload(&config).unwrap();
+    let effects = NAMES.map(|name| format!("\"{name}\"")).join(",");
+    for expected in [
+        r#""unique_id":"lantern-1""#,
+        r#""availability_topic":"home/lantern/availability""#,
+        r#""command_topic":"home/lantern/power/set""#,
+        r#""state_topic":"home/lantern/power""#,
+        r#""brightness_command_topic":"home/lantern/brightness/set""#,
+        r#""brightness_scale":255"#,
+        r#""effect_command_topic":"home/lantern/world/set""#,
+        r#""effect_state_topic":"home/lantern/world""#,
+        &format!(r#""effect_list":[{effects}]"#),
+    ] {
+        assert!(payload.contains(expected), "{expected} in {payload}");
+    }
+
+    let birth = message("homeassistant/status", "online");
+    assert!(discovery::is_birth(&config, &birth));
+    assert!(!discovery::is_birth(
+        &config,
+        &message("homeassistant/status", "offline")
+    ));
+    assert_eq!(config.command(&birth), None);
+}
+
+/// Blocking socket behind the async traits. Reads time out now and then
+/// so that `select` gets to poll the other futures.
+struct Stream(TcpStream);
+
+impl ErrorType for Stream {
+    type Error = ErrorKind;
+}
+
+impl Read for Stream {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, ErrorKind> {
+        poll_fn(|cx| match self.0.read(buf) {
+            Ok(read) => Poll::Ready(Ok(read)),
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+            Err(_) => Poll::Ready(Err(ErrorKind::Other)),
+        })
+        .await
+    }
+}
+
+impl Write for Stream {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, ErrorKind> {
+        self.0.write(buf).map_err(|_| ErrorKind::Other)
+    }
+}
+
+/// `MQTT_BROKER`, or mosquitto on this machine
+fn broker() -> Stream {
+    let address = std::env::var("MQTT_BROKER").unwrap_or("127.0.0.1:1883".to_string());
+    let stream = TcpStream::connect(&address)
+        .unwrap_or_else(|err| panic!("No MQTT broker at {address}: {err}"));
+    stream
+        .set_read_timeout(Some(Duration::from_millis(10)))
+        .unwrap();
+    Stream(stream)
+}
+
+/// Topic base and client id of a test, brokers may refuse a `/` in the id
+fn names(test: &str) -> (String, String) {
+    let id = format!("pleiades-{}-{test}", std::process::id());
+    (format!("pleiades-test/{id}"), id)
+}
+
+#[test]
+#[ignore = "needs an MQTT broker, see MQTT_BROKER"]
+fn round_trip() {
+    block_on(async {
+        let (base, id) = names("round_trip");
+        let topic = format!("{base}/world/set");
+        let (mut rx, mut tx) = ([0u8; 512], [0u8; 512]);
+        let mut client = Client::new(broker(), &mut rx, &mut tx);
+        let config = Config {
+            client_id: &id,
+            base: &base,
+            ..Config::default()
+        };
+
+        let availability = config.topic("availability").unwrap();
+        client
+            .connect(&config.connect(&availability))
+            .await
+            .unwrap();
+        client.subscribe(&[&topic]).await.unwrap();
+        client.publish(&message(&topic, "voronoi")).await.unwrap();
+
+        let Packet::Publish(received) = client.receive().await.unwrap() else {
+            panic!("Expected the message back");
+        };
+        assert_eq!(received, message(&topic, "voronoi"));
+        assert_eq!(config.command(&received), Some(Command::World(3)));
+    });
+}
+
+/// Fixed status, records the commands and hands out its gestures once
+struct Fake {
+    sent: Arc<Mutex<Vec<Command>>>,
+    gestures: Mutex<Vec<Command>>,
+}
+
+impl Lantern for Fake {
+    fn status(&self) -> Status {
+        STATUS
+    }
+
+    async fn send(&self, command: Command) {
+        self.sent.lock().unwrap().push(command);
+    }
+}
+
+impl Gestures for Fake {
+    async fn gesture(&self) -> Command {
+        let gesture = self.gestures.lock().unwrap().pop();
+        match gesture {
+            Some(gesture) => gesture,
+            None => std::future::pending().await,
+        }
+    }
+}
+
+#[test]
+#[ignore = "needs an MQTT broker, see MQTT_BROKER"]
+fn session() {
+    let (base, id) = names("session");
+    let sent = Arc::default();
+    let lantern = Fake {
+        sent: Arc::clone(&sent),
+        gestures: Mutex::new(vec![Command::Swipe(Direction::Left)]),
+    };
+
+    let prefix = format!("{base}/homeassistant");
+    let announce = discovery::payload(&Config {
+        client_id: &id,
+        base: &base,
+        discovery: Some(&prefix),
+        ..Config::default()
+    })
+    .unwrap();
+
+    block_on(async {
+        let (mut rx, mut tx) = ([0u8; PACKET_SIZE], [0u8; PACKET_SIZE]);
+        let mut observer = Client::new(broker(), &mut rx, &mut tx);
+        let observer_id = format!("{id}-observer");
+        let observer_config = Config {
+            client_id: &observer_id,
+            ..Config::default()
+        };
+        observer
+            .connect(&observer_config.connect(&observer_id))
+            .await
+            .unwrap();
+        observer.subscribe(&[&format!("{base}/#")]).await.unwrap();
+
+        let (lantern_id, lantern_base, lantern_prefix) = (id.clone(), base.clone(), prefix.clone());
+        std::thread::spawn(move || {
+            let (mut rx, mut tx) = ([0u8; PACKET_SIZE], [0u8; PACKET_SIZE]);
+            let mut client = Client::new(broker(), &mut rx, &mut tx);
+            let config = Config {
+                client_id: &lantern_id,
+                base: &lantern_base,
+                discovery: Some(&lantern_prefix),
+                ..Config::default()
+            };
+            let _ = block_on(client::session(&mut client, &config, &lantern));
+        });
+
+        let config_topic = format!("homeassistant/light/{id}/config");
+        let mut expected = vec![
+            (config_topic.as_str(), announce.as_str()),
+            ("availability", "online"),
+            ("world", "fire"),
+            ("power", "ON"),
+            ("brightness", "255"),
+            ("gesture", "swipe_left"),
+        ];
+        while !expected.is_empty() {
+            if let Packet::Publish(received) = observer.receive().await.unwrap() {
+                let leaf = received.topic.strip_prefix(&format!("{base}/")).unwrap();
+                let payload = std::str::from_utf8(received.payload).unwrap();
+                expected.retain(|message| *message != (leaf, payload));
+            }
+        }
+
+        let power = format!("{base}/power/set");
+        let world = format!("{base}/world/set");
+        observer.publish(&message(&power, "OFF")).await.unwrap();
+        observer.publish(&message(&world, "matrix")).await.unwrap();
+
+        // Home Assistant restarted, the lantern announces itself again
+        let status = format!("{prefix}/status");
+        observer.publish(&message(&status, "online")).await.unwrap();
+        loop {
+            if let Packet::Publish(received) = observer.receive().await.unwrap() {
+                if received.topic.ends_with(&config_topic) {
+                    assert_eq!(received.payload, announce.as_bytes());
+                    break;
+                }
+            }
+        }
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while sent.lock().unwrap().len() < 2 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    assert_eq!(
+        *sent.lock().unwrap(),
+        [Command::SwitchPower, Command::World(2)]
+    );
+}
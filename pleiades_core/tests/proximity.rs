@@ -0,0 +1,58 @@
+use pleiades_core::proximity::{
+    ConfigError, LedBoost, LedDrive, ProximityConfig, PulseLength, WaitTime,
+};
+
+#[test]
+fn registers() {
+    let config = ProximityConfig {
+        pulse_length: PulseLength::Us32,
+        pulses: 64,
+        ..Default::default()
+    };
+    assert_eq!(config.ppulse(), 0b1111_1111);
+    assert_eq!(ProximityConfig::default().ppulse(), 0b0100_0000);
+
+    let wait = |cycles| WaitTime {
+        cycles,
+        long: false,
+    };
+    assert_eq!(wait(1).wtime(), 0xFF);
+    assert_eq!(wait(256).wtime(), 0x00);
+}
+
+#[test]
+fn validate() {
+    let pulses = |pulses| ProximityConfig {
+        pulses,
+        ..Default::default()
+    };
+    assert_eq!(pulses(1).validate(), Ok(()));
+    assert_eq!(pulses(64).validate(), Ok(()));
+    assert_eq!(pulses(0).validate(), Err(ConfigError::Pulses(0)));
+
+    let wait = |cycles| ProximityConfig {
+        wait: Some(WaitTime { cycles, long: true }),
+        ..Default::default()
+    };
+    assert_eq!(wait(256).validate(), Ok(()));
+    assert_eq!(wait(0).validate(), Err(ConfigError::WaitCycles(0)));
+    assert_eq!(wait(257).validate(), Err(ConfigError::WaitCycles(257)));
+}
+
+#[test]
+fn power() {
+    assert_eq!(ProximityConfig::default().led_current_ma(), 300.0);
+
+    let config = ProximityConfig {
+        led_drive: LedDrive::Ma12_5,
+        led_boost: LedBoost::X2,
+        ..Default::default()
+    };
+    assert_eq!(config.led_current_ma(), 25.0);
+
+    let wait = WaitTime {
+        cycles: 10,
+        long: true,
+    };
+    assert!((wait.ms() - 333.6).abs() < 0.1);
+}
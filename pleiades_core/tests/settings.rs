@@ -0,0 +1,163 @@
+use embedded_storage::nor_flash::NorFlash;
+use pleiades_core::api::Status;
+use pleiades_core::settings::mock::{self, RamFlash};
+use pleiades_core::settings::{Error, Settings, State, Store};
+use pleiades_core::world::{Parameter, Switch, WORLDS};
+
+const SECTORS: u32 = 4;
+
+type Flash = RamFlash<4>;
+
+fn open(flash: Flash) -> Settings<Flash> {
+    Settings::new(Store::open(flash, 0, SECTORS).unwrap())
+}
+
+fn state(world: usize, brightness: u8) -> State {
+    let parameters = [Some(Parameter {
+        value: world as u8,
+        shade: -40,
+    }); WORLDS];
+    State {
+        status: Status {
+            world,
+            on: false,
+            brightness,
+        },
+        parameters,
+    }
+}
+
+#[test]
+fn round_trip() {
+    let mut settings = open(Flash::new());
+    // Nothing saved yet
+    assert_eq!(settings.state(), State::default());
+    assert_eq!(settings.proximity_offset(), None);
+
+    settings.save(&state(4, 30)).unwrap();
+    settings.set_proximity_offset(-12).unwrap();
+    assert_eq!(settings.state(), state(4, 30));
+
+    // After a power cycle
+    let settings = open(settings.release());
+    assert_eq!(settings.state(), state(4, 30));
+    assert_eq!(settings.proximity_offset(), Some(-12));
+}
+
+#[test]
+fn unchanged() {
+    let mut settings = open(Flash::new());
+    settings.save(&state(1, 200)).unwrap();
+    let flash = settings.release();
+    let written = flash.written();
+
+    let mut settings = open(flash);
+    settings.save(&state(1, 200)).unwrap();
+    assert_eq!(settings.release().written(), written);
+}
+
+#[test]
+fn invalid() {
+    let mut store = Store::open(Flash::new(), 0, SECTORS).unwrap();
+    assert_eq!(store.set(0xFF, &[1]), Err(Error::Invalid));
+    assert_eq!(store.set(1, &[0; 9]), Err(Error::Invalid));
+    for key in 0..16 {
+        store.set(key, &[key]).unwrap();
+    }
+    assert_eq!(store.set(16, &[0]), Err(Error::Full));
+    // Known keys still change
+    store.set(3, &[30]).unwrap();
+    assert_eq!(store.get(3), Some(&[30][..]));
+}
+
+#[test]
+fn wear() {
+    let mut settings = open(Flash::new());
+    for brightness in 0..10_000u32 {
+        settings.save(&state(2, (brightness % 256) as u8)).unwrap();
+    }
+    let flash = settings.release();
+
+    // Every sector took its turn
+    let erases = flash.erases();
+    let (least, most) = (erases.iter().min().unwrap(), erases.iter().max().unwrap());
+    assert!(*least > 0);
+    assert!(most - least <= 1, "{erases:?}");
+
+    let settings = open(flash);
+    assert_eq!(settings.state(), state(2, (9_999 % 256) as u8));
+}
+
+#[test]
+fn power_loss() {
+    // Cut the power at every byte of a run of saves that goes around the
+    // sectors a few times. The saved state is the one before or after
+    // the interrupted save, never a mix.
+    let mut settings = open(Flash::new());
+    settings.save(&state(0, 0)).unwrap();
+    let mut flash = settings.release();
+
+    for step in 1..3000u32 {
+        let before = open_state(&mut flash);
+        let after = state(step as usize % WORLDS, (step % 256) as u8);
+
+        flash.cut_after(Some(step as usize % 13));
+        let mut settings = open(flash);
+        let _ = settings.save(&after);
+        flash = settings.release();
+        flash.cut_after(None);
+
+        let saved = open_state(&mut flash);
+        // A save writes several keys, each one is old or new
+        assert!(
+            saved.status.world == before.status.world || saved.status.world == after.status.world,
+            "{step}"
+        );
+        assert!(
+            saved.status.brightness == before.status.brightness
+                || saved.status.brightness == after.status.brightness,
+            "{step}"
+        );
+
+        // The next save after the power is back goes through
+        let mut settings = open(flash);
+        settings.save(&after).unwrap();
+        assert_eq!(settings.state(), after);
+        flash = settings.release();
+        assert_eq!(open_state(&mut flash), after, "{step}");
+    }
+}
+
+fn open_state(flash: &mut Flash) -> State {
+    let settings = open(core::mem::take(flash));
+    let state = settings.state();
+    *flash = settings.release();
+    state
+}
+
+#[test]
+fn foreign() {
+    // The calibration record of earlier firmware, and a header cut short,
+    // are not taken for settings
+    let mut flash = Flash::new();
+    flash.write(0, b"POFF\x01\x05\xfa\xff").unwrap();
+    flash.write(mock::SECTOR_SIZE as u32, b"PLKV\x03").unwrap();
+    let mut settings = open(flash);
+    assert_eq!(settings.state(), State::default());
+    settings.set_proximity_offset(5).unwrap();
+    assert_eq!(open(settings.release()).proximity_offset(), Some(5));
+}
+
+#[test]
+fn switch() {
+    let mut parameters = [None; WORLDS];
+    parameters[2] = Some(Parameter { value: 4, shade: 0 });
+
+    let switch = Switch::restore(3, true, parameters);
+    assert_eq!((switch.world(), switch.is_on()), (3, true));
+    assert_eq!(switch.parameters(), parameters);
+
+    // Off, with the world to come back to
+    let switch = Switch::restore(5, false, parameters);
+    assert_eq!((switch.world(), switch.is_on()), (5, false));
+}
@@ -0,0 +1,282 @@
+use embassy_time::{Duration, Instant};
+use pleiades_core::stream::{
+    self, artnet, ddp, e131, tpm2, wled, Dmx, Error, Frame, Layout, Raw, Watchdog, TIMEOUT,
+};
+use smart_leds::RGB8;
+
+/// E1.31 data packet as a source sends it
+fn e131_packet(universe: u16, data: &[u8], options: u8) -> Vec<u8> {
+    let mut packet = vec![0u8; 126];
+    packet[0..2].copy_from_slice(&[0x00, 0x10]);
+    packet[4..16].copy_from_slice(b"ASC-E1.17\0\0\0");
+    packet[18..22].copy_from_slice(&[0, 0, 0, 4]);
+    packet[40..44].copy_from_slice(&[0, 0, 0, 2]);
+    packet[44..51].copy_from_slice(b"xLights");
+    packet[108] = 100;
+    packet[112] = options;
+    packet[113..115].copy_from_slice(&universe.to_be_bytes());
+    packet[117] = 0x02;
+    packet[118] = 0xA1;
+    packet[121..123].copy_from_slice(&1u16.to_be_bytes());
+    packet[123..125].copy_from_slice(&(data.len() as u16 + 1).to_be_bytes());
+    packet.extend_from_slice(data);
+    packet
+}
+
+fn artnet_packet(universe: u16, data: &[u8]) -> Vec<u8> {
+    let mut packet = b"Art-Net\0".to_vec();
+    packet.extend_from_slice(&0x5000u16.to_le_bytes());
+    packet.extend_from_slice(&[0, 14, 1, 0]);
+    packet.extend_from_slice(&universe.to_le_bytes());
+    packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    packet.extend_from_slice(data);
+    packet
+}
+
+#[test]
+fn e131() {
+    let data = [1, 2, 3, 4, 5, 6];
+    let packet = e131_packet(7, &data, 0);
+    assert_eq!(
+        e131::parse(&packet),
+        Ok(Dmx {
+            universe: 7,
+            data: &data
+        })
+    );
+    assert_eq!(stream::parse(&packet).map(|dmx| dmx.universe), Ok(7));
+
+    assert_eq!(
+        e131::parse(&packet[..packet.len() - 1]),
+        Err(Error::Truncated)
+    );
+    assert_eq!(e131::parse(&packet[..100]), Err(Error::Truncated));
+    // Preview data is for visualizers
+    assert_eq!(
+        e131::parse(&e131_packet(7, &data, 0x20)),
+        Err(Error::Unsupported)
+    );
+    // Per-address priorities
+    let mut priorities = packet.clone();
+    priorities[125] = 0xDD;
+    assert_eq!(e131::parse(&priorities), Err(Error::Unsupported));
+    // Synchronization
+    let mut sync = packet;
+    sync[21] = 0x08;
+    assert_eq!(e131::parse(&sync), Err(Error::Unsupported));
+
+    assert_eq!(e131::multicast(1), [239, 255, 0, 1]);
+    assert_eq!(e131::multicast(0x0203), [239, 255, 2, 3]);
+}
+
+#[test]
+fn artnet() {
+    let data = [9, 8, 7];
+    // Net 1, sub-net 2, universe 3
+    let packet = artnet_packet(0x0123, &data);
+    assert_eq!(
+        artnet::parse(&packet),
+        Ok(Dmx {
+            universe: 0x0123,
+            data: &data
+        })
+    );
+    assert_eq!(stream::parse(&packet).map(|dmx| dmx.universe), Ok(0x0123));
+    assert_eq!(artnet::parse(&packet[..20]), Err(Error::Truncated));
+
+    // ArtPoll
+    let mut poll = packet;
+    poll[8..10].copy_from_slice(&0x2000u16.to_le_bytes());
+    assert_eq!(artnet::parse(&poll), Err(Error::Unsupported));
+}
+
+fn dmx(universe: u16, data: &[u8]) -> Dmx<'_> {
+    Dmx { universe, data }
+}
+
+#[test]
+fn layout() {
+    // Two universes of 170 pixels cover the 256 of the lantern
+    let layout = Layout::default();
+    let mut frame: Frame<256> = Frame::new();
+    let first: Vec<u8> = (0..510).map(|channel| (channel % 256) as u8).collect();
+    let second = [10u8; 512];
+
+    assert!(!frame.apply(&layout, &dmx(0, &first)));
+    assert!(frame.apply(&layout, &dmx(1, &first)));
+    assert!(frame.apply(&layout, &dmx(2, &second)));
+    assert!(!frame.apply(&layout, &dmx(3, &second)));
+
+    assert_eq!(frame.pixels[0], RGB8::new(0, 1, 2));
+    assert_eq!(frame.pixels[169], RGB8::new(251, 252, 253));
+    assert_eq!(frame.pixels[170], RGB8::new(10, 10, 10));
+    assert_eq!(frame.pixels[255], RGB8::new(10, 10, 10));
+    assert_eq!(layout.universes(256), 1..3);
+    assert_eq!(layout.universes(170), 1..2);
+
+    // A pixel split over two universes of 512 channels
+    let layout = Layout {
+        universe: 5,
+        channels: 512,
+    };
+    let mut frame: Frame<256> = Frame::new();
+    frame.apply(&layout, &dmx(5, &[1; 512]));
+    frame.apply(&layout, &dmx(6, &[2; 3]));
+    assert_eq!(frame.pixels[170], RGB8::new(1, 1, 2));
+    assert_eq!(frame.pixels[171], RGB8::new(2, 2, 0));
+    assert_eq!(layout.universes(256), 5..7);
+}
+
+fn ddp_packet(flags: u8, offset: u32, data: &[u8]) -> Vec<u8> {
+    let mut packet = vec![flags, 1, 0x0B, 1];
+    packet.extend_from_slice(&offset.to_be_bytes());
+    packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    packet.extend_from_slice(data);
+    packet
+}
+
+#[test]
+fn ddp() {
+    let data = [1, 2, 3, 4, 5, 6];
+    assert_eq!(
+        ddp::parse(&ddp_packet(0x41, 300, &data)),
+        Ok(Raw {
+            offset: 300,
+            data: &data,
+            last: true
+        })
+    );
+    assert_eq!(
+        ddp::parse(&ddp_packet(0x40, 0, &data)).map(|raw| raw.last),
+        Ok(false)
+    );
+    // Timecode before the data
+    let mut timecode = ddp_packet(0x51, 0, &[]);
+    timecode[8..10].copy_from_slice(&3u16.to_be_bytes());
+    timecode.extend_from_slice(&[0, 0, 0, 1, 7, 8, 9]);
+    assert_eq!(
+        ddp::parse(&timecode).map(|raw| raw.data),
+        Ok(&[7, 8, 9][..])
+    );
+
+    let packet = ddp_packet(0x41, 0, &data);
+    assert_eq!(ddp::parse(&packet[..12]), Err(Error::Truncated));
+    assert_eq!(ddp::parse(&packet[..8]), Err(Error::Truncated));
+    // Queries, other versions, RGBW and JSON control
+    assert_eq!(
+        ddp::parse(&ddp_packet(0x43, 0, &data)),
+        Err(Error::Unsupported)
+    );
+    assert_eq!(
+        ddp::parse(&ddp_packet(0x81, 0, &data)),
+        Err(Error::Unsupported)
+    );
+    let mut rgbw = packet.clone();
+    rgbw[2] = 0x1B;
+    assert_eq!(ddp::parse(&rgbw), Err(Error::Unsupported));
+    let mut control = packet;
+    control[3] = 246;
+    assert_eq!(ddp::parse(&control), Err(Error::Unsupported));
+}
+
+fn tpm2_packet(number: u8, count: u8, data: &[u8]) -> Vec<u8> {
+    let mut packet = vec![0x9C, 0xDA];
+    packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    packet.extend_from_slice(&[number, count]);
+    packet.extend_from_slice(data);
+    packet.push(0x36);
+    packet
+}
+
+#[test]
+fn tpm2() {
+    let data = [1u8; 384];
+    assert_eq!(
+        tpm2::parse(&tpm2_packet(1, 2, &data)),
+        Ok(Raw {
+            offset: 0,
+            data: &data,
+            last: false
+        })
+    );
+    assert_eq!(
+        tpm2::parse(&tpm2_packet(2, 2, &data)).map(|raw| (raw.offset, raw.last)),
+        Ok((384, true))
+    );
+    // Without the end byte
+    let packet = tpm2_packet(1, 1, &data);
+    assert!(tpm2::parse(&packet[..packet.len() - 1]).is_ok());
+    assert_eq!(
+        tpm2::parse(&packet[..packet.len() - 2]),
+        Err(Error::Truncated)
+    );
+    // Commands
+    let mut command = packet;
+    command[1] = 0xC0;
+    assert_eq!(tpm2::parse(&command), Err(Error::Unsupported));
+}
+
+#[test]
+fn wled() {
+    assert_eq!(
+        wled::parse(&[2, 1, 10, 20, 30]),
+        Ok(Raw {
+            offset: 0,
+            data: &[10, 20, 30],
+            last: true
+        })
+    );
+    assert_eq!(
+        wled::parse(&[4, 255, 0x01, 0x00, 10, 20, 30]).map(|raw| raw.offset),
+        Ok(768)
+    );
+    assert_eq!(wled::parse(&[4, 1, 0]), Err(Error::Truncated));
+    assert_eq!(wled::parse(&[]), Err(Error::Truncated));
+    // WARLS, DRGBW and a sync notification
+    assert_eq!(wled::parse(&[1, 1, 0, 10, 20, 30]), Err(Error::Unsupported));
+    assert_eq!(
+        wled::parse(&[3, 1, 10, 20, 30, 40]),
+        Err(Error::Unsupported)
+    );
+    assert_eq!(wled::parse(&[0; 24]), Err(Error::Unsupported));
+}
+
+#[test]
+fn write() {
+    // A DDP frame of 256 pixels in two packets, the second one pushes it
+    let mut frame: Frame<256> = Frame::new();
+    let first = [10u8; 384];
+    let second = [20u8; 384];
+    assert!(!frame.write(&ddp::parse(&ddp_packet(0x40, 0, &first)).unwrap()));
+    assert!(frame.write(&ddp::parse(&ddp_packet(0x41, 384, &second)).unwrap()));
+    assert_eq!(frame.pixels[127], RGB8::new(10, 10, 10));
+    assert_eq!(frame.pixels[128], RGB8::new(20, 20, 20));
+
+    // Past the last pixel
+    let raw = Raw {
+        offset: 767,
+        data: &[1, 2, 3],
+        last: true,
+    };
+    assert!(frame.write(&raw));
+    assert_eq!(frame.pixels[255], RGB8::new(20, 20, 1));
+}
+
+#[test]
+fn watchdog() {
+    let start = Instant::from_secs(10);
+    let mut watchdog = Watchdog::new();
+    assert!(!watchdog.is_streaming());
+    assert!(!watchdog.expired(start));
+
+    assert!(watchdog.feed(start));
+    assert!(!watchdog.feed(start + Duration::from_millis(20)));
+    assert!(watchdog.is_streaming());
+    assert!(!watchdog.expired(start + TIMEOUT));
+
+    // Falls back once, and starts again with the next frame
+    assert!(watchdog.expired(start + Duration::from_millis(20) + TIMEOUT));
+    assert!(!watchdog.expired(start + TIMEOUT * 2));
+    assert!(!watchdog.is_streaming());
+    assert!(watchdog.feed(start + TIMEOUT * 3));
+}
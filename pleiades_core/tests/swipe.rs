@@ -0,0 +1,129 @@
+use pleiades_core::gesture::{Command, Direction};
+use pleiades_core::swipe::{Dataset, SwipeRecognizer};
+
+/// Dataset of a hand over one side of the sensor at some level
+type Side = fn(u8) -> Dataset;
+
+/// A hand passing over the sensor: it covers the `from` photodiodes
+/// first, then all of them, then the `to` photodiodes
+fn pass(from: Side, to: Side) -> Vec<Dataset> {
+    let both = |a: Dataset, b: Dataset| Dataset {
+        up: a.up.max(b.up),
+        down: a.down.max(b.down),
+        left: a.left.max(b.left),
+        right: a.right.max(b.right),
+    };
+    [40, 90, 140]
+        .into_iter()
+        .map(|level| both(from(level), Dataset::default()))
+        .chain([both(from(160), to(160))])
+        .chain([140, 90, 40].into_iter().map(to))
+        .collect()
+}
+
+fn up(level: u8) -> Dataset {
+    Dataset {
+        up: level,
+        left: level / 4,
+        right: level / 4,
+        ..Default::default()
+    }
+}
+
+fn down(level: u8) -> Dataset {
+    Dataset {
+        down: level,
+        left: level / 4,
+        right: level / 4,
+        ..Default::default()
+    }
+}
+
+fn left(level: u8) -> Dataset {
+    Dataset {
+        left: level,
+        up: level / 4,
+        down: level / 4,
+        ..Default::default()
+    }
+}
+
+fn right(level: u8) -> Dataset {
+    Dataset {
+        right: level,
+        up: level / 4,
+        down: level / 4,
+        ..Default::default()
+    }
+}
+
+fn replay(recognizer: &mut SwipeRecognizer, datasets: &[Dataset]) -> Vec<Command> {
+    let mut commands = Vec::new();
+    for &dataset in datasets {
+        recognizer.next(dataset);
+        commands.extend(recognizer.command());
+    }
+    recognizer.end();
+    commands.extend(recognizer.command());
+    commands
+}
+
+#[test]
+fn swipes() {
+    let mut recognizer = SwipeRecognizer::default();
+
+    let cases: [(Side, Side, Direction); 4] = [
+        (down, up, Direction::Up),
+        (up, down, Direction::Down),
+        (right, left, Direction::Left),
+        (left, right, Direction::Right),
+    ];
+    for (from, to, direction) in cases {
+        assert_eq!(
+            replay(&mut recognizer, &pass(from, to)),
+            [Command::Swipe(direction)],
+            "{direction:?}"
+        );
+    }
+}
+
+#[test]
+fn near_and_far() {
+    let mut recognizer = SwipeRecognizer::default();
+    let close = Dataset {
+        up: 230,
+        down: 225,
+        left: 228,
+        right: 232,
+    };
+    let datasets: Vec<Dataset> = (0..30).map(|_| close).collect();
+
+    // Near once, Far instead of a swipe when the hand leaves
+    assert_eq!(
+        replay(&mut recognizer, &datasets),
+        [
+            Command::Swipe(Direction::Near),
+            Command::Swipe(Direction::Far)
+        ]
+    );
+}
+
+#[test]
+fn hover_and_noise() {
+    let mut recognizer = SwipeRecognizer::default();
+    let hover = Dataset {
+        up: 80,
+        down: 78,
+        left: 81,
+        right: 79,
+    };
+    let noise = Dataset {
+        up: 3,
+        down: 9,
+        left: 0,
+        right: 1,
+    };
+
+    assert_eq!(replay(&mut recognizer, &[hover; 12]), []);
+    assert_eq!(replay(&mut recognizer, &[noise, up(8), noise]), []);
+}
@@ -0,0 +1,162 @@
+use pleiades_core::api::wled::{handle, Device};
+use pleiades_core::api::{Request, Status, BODY_SIZE};
+use pleiades_core::gesture::Command;
+use pleiades_core::world::stream;
+
+const STATUS: Status = Status {
+    world: 0,
+    on: true,
+    brightness: 255,
+};
+
+const DEVICE: Device = Device {
+    name: "Pleiades",
+    leds: 256,
+    mac: [0x02, 0x00, 0x00, 0xAB, 0xCD, 0xEF],
+};
+
+fn call(method: &str, path: &str, body: &str, status: Status) -> (u16, String, Vec<Command>) {
+    let raw = format!(
+        "{method} {path} HTTP/1.1\r\nHost: pleiades\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    );
+    let request = Request::parse(raw.as_bytes()).expect("Invalid request");
+    let (response, commands) = handle(&request, status, &DEVICE);
+    (
+        response.status,
+        response.body.to_string(),
+        commands.into_iter().collect(),
+    )
+}
+
+#[test]
+fn state() {
+    let (status, body, commands) = call("GET", "/json/state", "", STATUS);
+    assert_eq!(status, 200);
+    assert!(body.starts_with(r#"{"on":true,"bri":255,"#), "{body}");
+    assert!(body.contains(r#""len":256,"#), "{body}");
+    assert!(body.contains(r#""fx":0,"#), "{body}");
+    assert!(commands.is_empty());
+
+    let (_, body, _) = call(
+        "GET",
+        "/json/state",
+        "",
+        Status {
+            world: 3,
+            on: false,
+            brightness: 40,
+        },
+    );
+    assert!(body.starts_with(r#"{"on":false,"bri":40,"#), "{body}");
+    assert!(body.contains(r#""fx":3,"#), "{body}");
+}
+
+#[test]
+fn info() {
+    let (status, body, _) = call("GET", "/json/info", "", STATUS);
+    assert_eq!(status, 200);
+    assert!(body.contains(r#""leds":{"count":256,"#), "{body}");
+    assert!(
+        body.contains(&format!(r#""fps":{},"#, stream::FPS)),
+        "{body}"
+    );
+    assert!(body.contains(r#""name":"Pleiades""#), "{body}");
+    assert!(body.contains(r#""fxcount":6,"#), "{body}");
+    assert!(body.contains(r#""brand":"WLED""#), "{body}");
+    assert!(body.contains(r#""mac":"020000abcdef""#), "{body}");
+
+    assert_eq!(
+        call("GET", "/json/effects", "", STATUS),
+        (
+            200,
+            r#"["fire","northen_light","matrix","voronoi","starry_night","solid"]"#.into(),
+            vec![]
+        )
+    );
+
+    // Everything at once still fits
+    for path in ["/json", "/json/si"] {
+        let (status, body, _) = call("GET", path, "", STATUS);
+        assert_eq!(status, 200, "{path}");
+        assert!(body.starts_with(r#"{"state":{"on":true"#), "{body}");
+        assert!(body.len() < BODY_SIZE);
+    }
+}
+
+#[test]
+fn change() {
+    assert_eq!(
+        call("POST", "/json/state", r#"{"bri":64}"#, STATUS),
+        (
+            200,
+            r#"{"success":true}"#.into(),
+            vec![Command::Brightness(64)]
+        )
+    );
+
+    // The power is set whatever the status says, "t" toggles it
+    let (_, _, commands) = call("POST", "/json/state", r#"{"on":true}"#, STATUS);
+    assert_eq!(commands, [Command::Power(true)]);
+    let (_, _, commands) = call("POST", "/json/state", r#"{"on":false}"#, STATUS);
+    assert_eq!(commands, [Command::Power(false)]);
+    let (_, body, commands) = call("POST", "/json/state", r#"{"on":"t","v":true}"#, STATUS);
+    assert_eq!(commands, [Command::SwitchPower]);
+    assert!(body.starts_with(r#"{"on":false,"bri":255,"#), "{body}");
+
+    // Zero brightness is off, the brightness is kept
+    let (_, body, commands) = call("POST", "/json/state", r#"{"bri":0,"v":true}"#, STATUS);
+    assert_eq!(commands, [Command::Power(false)]);
+    assert!(body.starts_with(r#"{"on":false,"bri":255,"#), "{body}");
+
+    // A single segment
+    let (_, _, commands) = call("POST", "/json/state", r#"{"seg":{"id":0,"fx":4}}"#, STATUS);
+    assert_eq!(commands, [Command::World(4)]);
+
+    // Unknown fields are left alone, the answer is the state with "v"
+    let (status, body, commands) = call(
+        "POST",
+        "/json/state",
+        r#"{"on":true,"bri":100,"transition":7,"seg":[{"id":0,"fx":2,"sx":100}],"v":true}"#,
+        Status {
+            on: false,
+            ..STATUS
+        },
+    );
+    assert_eq!(status, 200);
+    assert!(body.starts_with(r#"{"on":true,"bri":100,"#), "{body}");
+    assert!(body.contains(r#""fx":2,"#), "{body}");
+    // The world switches the power on
+    assert_eq!(
+        commands,
+        [
+            Command::World(2),
+            Command::Brightness(100),
+            Command::Power(true)
+        ]
+    );
+
+    // Off right after the effect changes
+    let (_, _, commands) = call("POST", "/json", r#"{"on":false,"seg":[{"fx":5}]}"#, STATUS);
+    assert_eq!(commands, [Command::World(5), Command::Power(false)]);
+}
+
+#[test]
+fn errors() {
+    let (status, _, commands) = call("POST", "/json/state", r#"{"seg":[{"fx":6}]}"#, STATUS);
+    assert_eq!(status, 400);
+    assert!(commands.is_empty());
+
+    let (status, _, _) = call("POST", "/json/state", r#"{"bri":300}"#, STATUS);
+    assert_eq!(status, 400);
+    let (status, _, _) = call("POST", "/json/state", r#"{"on":"x"}"#, STATUS);
+    assert_eq!(status, 400);
+    let (status, _, _) = call("POST", "/json/state", r#"{"seg":3}"#, STATUS);
+    assert_eq!(status, 400);
+
+    let (status, _, _) = call("PUT", "/json/info", "", STATUS);
+    assert_eq!(status, 405);
+
+    let (status, _, _) = call("GET", "/json/nodes", "", STATUS);
+    assert_eq!(status, 404);
+}
@@ -0,0 +1,178 @@
+//! Serves the lantern's HTTP/JSON and WLED APIs from Linux over a TAP interface, with
+//! the same embassy-net stack as the Pico W, and talks to an MQTT broker
+//! when given one. A stand-in lantern prints the commands it receives and
+//! keeps the status they lead to.
+//!
+//! `cargo run -- [--tap NAME] [--address IP/PREFIX] [--gateway IP] [--dhcp]
+//! [--broker IP:PORT]`
+
+#![feature(type_alias_impl_trait)]
+
+use embassy_executor::{Executor, Spawner};
+use embassy_net::{
+    Config, IpEndpoint, Ipv4Address, Ipv4Cidr, Stack, StackResources, StaticConfigV4,
+};
+use embassy_net_tuntap::TunTapDevice;
+use pleiades_core::api::{server, Lantern, Status};
+use pleiades_core::gesture::Command;
+use pleiades_core::mqtt::{self, client::Gestures};
+use pleiades_core::world::NAMES;
+use static_cell::StaticCell;
+use std::cell::Cell;
+use std::process::exit;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const USAGE: &str = "usage: pleiades_host [--tap NAME] [--address IP/PREFIX] [--gateway IP] [--dhcp] [--broker IP:PORT]";
+/// Port of the API, 80 needs root
+const PORT: u16 = 8080;
+/// LEDs of the lantern it stands in for
+const LEDS: u16 = 256;
+
+/// Prints and applies commands the way the main loop of the firmware does
+struct Host {
+    status: Cell<Status>,
+}
+
+impl Lantern for Host {
+    fn status(&self) -> Status {
+        self.status.get()
+    }
+
+    async fn send(&self, command: Command) {
+        let mut status = self.status.get();
+        match command {
+            Command::World(world) => {
+                status.world = world;
+                status.on = true;
+            }
+            Command::SwitchPower => status.on = !status.on,
+            Command::Power(on) => status.on = on,
+            Command::Brightness(brightness) => status.brightness = brightness,
+            _ => {}
+        }
+        self.status.set(status);
+        println!(
+            "{command:?} -> {} {} {}",
+            NAMES[status.world],
+            if status.on { "on" } else { "off" },
+            status.brightness
+        );
+    }
+}
+
+/// No sensor, no gestures
+impl Gestures for Host {
+    async fn gesture(&self) -> Command {
+        std::future::pending().await
+    }
+}
+
+#[embassy_executor::task]
+async fn net_task(stack: &'static Stack<TunTapDevice>) -> ! {
+    stack.run().await
+}
+
+#[embassy_executor::task]
+async fn api_task(stack: &'static Stack<TunTapDevice>, host: &'static Host) -> ! {
+    stack.wait_config_up().await;
+    if let Some(config) = stack.config_v4() {
+        println!(
+            "Serving on http://{}:{PORT}/api/status",
+            config.address.address()
+        );
+    }
+    server::serve(stack, PORT, host, LEDS).await
+}
+
+#[embassy_executor::task]
+async fn mqtt_task(
+    stack: &'static Stack<TunTapDevice>,
+    broker: IpEndpoint,
+    host: &'static Host,
+) -> ! {
+    println!("Publishing to {broker}");
+    mqtt::client::run(stack, broker, &mqtt::Config::default(), host).await
+}
+
+fn main() {
+    let (tap, config, broker) = parse().unwrap_or_else(|message| {
+        eprintln!("{message}\n{USAGE}");
+        exit(2);
+    });
+
+    static EXECUTOR: StaticCell<Executor> = StaticCell::new();
+    EXECUTOR
+        .init(Executor::new())
+        .run(|spawner| start(spawner, tap, config, broker));
+}
+
+fn start(spawner: Spawner, tap: String, config: Config, broker: Option<IpEndpoint>) {
+    let device = TunTapDevice::new(&tap).unwrap_or_else(|err| {
+        eprintln!("{tap}: {err}");
+        exit(1);
+    });
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_nanos() as u64)
+        .unwrap_or_default();
+
+    static RESOURCES: StaticCell<StackResources<4>> = StaticCell::new();
+    static STACK: StaticCell<Stack<TunTapDevice>> = StaticCell::new();
+    let stack = &*STACK.init(Stack::new(
+        device,
+        config,
+        RESOURCES.init(StackResources::new()),
+        seed,
+    ));
+
+    static HOST: StaticCell<Host> = StaticCell::new();
+    let host = &*HOST.init(Host {
+        status: Cell::new(Status {
+            world: 0,
+            on: true,
+            brightness: u8::MAX,
+        }),
+    });
+
+    spawner.must_spawn(net_task(stack));
+    spawner.must_spawn(api_task(stack, host));
+    if let Some(broker) = broker {
+        spawner.must_spawn(mqtt_task(stack, broker, host));
+    }
+}
+
+fn parse() -> Result<(String, Config, Option<IpEndpoint>), String> {
+    let mut tap = "tap0".to_string();
+    let mut address = Ipv4Cidr::new(Ipv4Address::new(192, 168, 69, 2), 24);
+    let mut gateway = Some(Ipv4Address::new(192, 168, 69, 1));
+    let mut dhcp = false;
+    let mut broker = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--dhcp" {
+            dhcp = true;
+            continue;
+        }
+
+        let value = args.next().ok_or(format!("{arg} expects a value"))?;
+        let invalid = |_| format!("{arg} does not take {value}");
+        match arg.as_str() {
+            "--tap" => tap = value.clone(),
+            "--address" => address = value.parse().map_err(invalid)?,
+            "--gateway" => gateway = Some(value.parse().map_err(invalid)?),
+            "--broker" => broker = Some(value.parse().map_err(invalid)?),
+            _ => return Err(format!("Unknown option {arg}")),
+        }
+    }
+
+    let config = match dhcp {
+        true => Config::dhcpv4(Default::default()),
+        false => Config::ipv4_static(StaticConfigV4 {
+            address,
+            gateway,
+            dns_servers: Default::default(),
+        }),
+    };
+    Ok((tap, config, broker))
+}
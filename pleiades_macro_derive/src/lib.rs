@@ -29,15 +29,23 @@ fn impl_pleiades_flush(ast: &DeriveInput) -> TokenStream {
     gen.into()
 }
 
+/// `Fire, Matrix; Empty`, the worlds of the rotation in switching order,
+/// then after `;` the ones only shown on purpose
 struct Args {
-    variants: Vec<Ident>,
+    rotation: Vec<Ident>,
+    others: Vec<Ident>,
 }
 
 impl Parse for Args {
     fn parse(input: ParseStream) -> Result<Self> {
-        let variants = Punctuated::<Ident, Token![,]>::parse_terminated(input)?;
+        let rotation = Punctuated::<Ident, Token![,]>::parse_separated_nonempty(input)?;
+        let others = match input.parse::<Option<Token![;]>>()? {
+            Some(_) => Punctuated::<Ident, Token![,]>::parse_terminated(input)?,
+            None => Punctuated::new(),
+        };
         Ok(Args {
-            variants: variants.into_iter().collect(),
+            rotation: rotation.into_iter().collect(),
+            others: others.into_iter().collect(),
         })
     }
 }
@@ -53,13 +61,27 @@ pub fn enum_world(attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut new_world_funcs = quote! {};
     let mut match_blocks = quote! {};
     let mut on_directions_funcs = quote! {};
+    let mut parameter_funcs = quote! {};
+    let mut set_parameter_funcs = quote! {};
+    let mut names = quote! {};
+    let mut rotation_arms = quote! {};
 
-    for variant in &args.variants {
+    for (index, variant) in args.rotation.iter().enumerate() {
+        let snake = variant.to_string().to_snake_case();
+        let func_name = format_ident!("{}_new", snake);
+        names.extend(quote! { #snake, });
+        rotation_arms.extend(quote! {
+            #index => Self::#func_name(led, rng),
+        });
+    }
+    let worlds = args.rotation.len();
+
+    for variant in args.rotation.iter().chain(&args.others) {
         let snake = format_ident!("{}", variant.to_string().to_snake_case());
         let func_name = format_ident!("{}_new", snake);
         let func_code = quote! {
-            pub fn #func_name (led: &'led mut Led ) -> Self {
-                let #snake = #snake::#variant::new(led);
+            pub fn #func_name (led: &'led mut Led, rng: &'led mut Rng) -> Self {
+                let #snake = #snake::#variant::new(led, rng);
                 World::#variant(#snake)
             }
         };
@@ -77,16 +99,37 @@ pub fn enum_world(attr: TokenStream, item: TokenStream) -> TokenStream {
             Self::#variant(#snake) => #snake.on_direction(direction),
         };
         on_directions_funcs.extend(on_direction_func_code);
+
+        parameter_funcs.extend(quote! {
+            Self::#variant(#snake) => #snake.parameter(),
+        });
+        set_parameter_funcs.extend(quote! {
+            Self::#variant(#snake) => #snake.set_parameter(parameter),
+        });
     }
 
     let gen = quote! {
+        /// Worlds of the rotation
+        pub const WORLDS: usize = #worlds;
+
+        /// Worlds of the rotation, in switching order
+        pub const NAMES: [&str; WORLDS] = [#names];
+
         #item
 
         impl #impl_generics #name #ty_generics #where_clause
         {
             #new_world_funcs
 
-            pub async fn tick(world: &mut World<'led, Led, C, L, N, N2>) {
+            /// The world at `index` in `NAMES`
+            pub fn rotation_new(index: usize, led: &'led mut Led, rng: &'led mut Rng) -> Self {
+                match index {
+                    #rotation_arms
+                    _ => panic!("World index out of bounds"),
+                }
+            }
+
+            pub async fn tick(world: &mut World<'led, Led, Rng, C, L, N, N2>) {
                 match world {
                     #match_blocks
                 }
@@ -100,6 +143,18 @@ pub fn enum_world(attr: TokenStream, item: TokenStream) -> TokenStream {
                     #on_directions_funcs
                 }
             }
+
+            fn parameter(&self) -> Option<Parameter> {
+                match self {
+                    #parameter_funcs
+                }
+            }
+
+            fn set_parameter(&mut self, parameter: Parameter) {
+                match self {
+                    #set_parameter_funcs
+                }
+            }
         }
     };
 
@@ -0,0 +1,120 @@
+//! Records a world and encodes it as an animated GIF or APNG:
+//!
+//! `cargo run --bin export -- <world> <out.gif|out.png> [--seconds N] [--dot N] [--seed N] [--wiring]`
+
+use embassy_time::Duration;
+use pleiades_core::random::SeededRandom;
+use pleiades_core::world::World;
+use pleiades_sim::clock;
+use pleiades_sim::export::{Export, Layout};
+use pleiades_sim::record::RecordingMatrix;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::process::exit;
+
+const NUM_LEDS_LINE: usize = 16;
+const NUM_LEDS_COLUMN: usize = 16;
+const NUM_LEDS: usize = NUM_LEDS_LINE * NUM_LEDS_COLUMN;
+
+const USAGE: &str =
+    "usage: export <world> <out.gif|out.png> [--seconds N] [--dot N] [--seed N] [--wiring]
+worlds: fire, northen_light, matrix, voronoi, starry_night, solid, diagnostic";
+
+struct Args {
+    world: String,
+    out: PathBuf,
+    seconds: u64,
+    dot: usize,
+    seed: u64,
+    layout: Layout,
+}
+
+fn main() {
+    let args = parse().unwrap_or_else(|message| {
+        eprintln!("{message}\n{USAGE}");
+        exit(2);
+    });
+
+    let mut led_matrix: RecordingMatrix<NUM_LEDS_COLUMN, NUM_LEDS_LINE> = RecordingMatrix::new();
+    let mut rng = SeededRandom::new(args.seed);
+
+    let mut world: World<'_, _, _, NUM_LEDS_COLUMN, NUM_LEDS_LINE, NUM_LEDS, { 2 * NUM_LEDS }> =
+        match args.world.as_str() {
+            "fire" => World::fire_new(&mut led_matrix, &mut rng),
+            "northen_light" => World::northen_light_new(&mut led_matrix, &mut rng),
+            "matrix" => World::matrix_new(&mut led_matrix, &mut rng),
+            "voronoi" => World::voronoi_new(&mut led_matrix, &mut rng),
+            "starry_night" => World::starry_night_new(&mut led_matrix, &mut rng),
+            "solid" => World::solid_new(&mut led_matrix, &mut rng),
+            "diagnostic" => World::diagnostic_new(&mut led_matrix, &mut rng),
+            world => {
+                eprintln!("Unknown world {world}\n{USAGE}");
+                exit(2);
+            }
+        };
+
+    // Every tick lasts one period of the world's ticker on the virtual clock
+    let duration = Duration::from_secs(args.seconds);
+    let mut delay = Duration::from_ticks(0);
+    let mut elapsed = Duration::from_ticks(0);
+    while elapsed < duration {
+        delay = clock::run(World::tick(&mut world));
+        elapsed += delay;
+    }
+
+    let export = Export {
+        dot: args.dot,
+        delay,
+        layout: args.layout,
+    };
+    let frames = led_matrix.frames();
+    let mut out = BufWriter::new(File::create(&args.out).expect("Failed to create the output"));
+    let result = match args
+        .out
+        .extension()
+        .and_then(|extension| extension.to_str())
+    {
+        Some("gif") => export.gif(&mut out, frames),
+        _ => export.apng(&mut out, frames),
+    };
+    result.expect("Failed to encode the animation");
+
+    println!(
+        "{} frames, {} ms each, seed {}",
+        frames.len(),
+        delay.as_millis(),
+        args.seed
+    );
+}
+
+fn parse() -> Result<Args, String> {
+    let mut args = std::env::args().skip(1);
+    let world = args.next().ok_or("Missing world")?;
+    let out = args.next().ok_or("Missing output file")?.into();
+
+    let mut parsed = Args {
+        world,
+        out,
+        seconds: 5,
+        dot: 16,
+        seed: 0,
+        layout: Layout::Logical,
+    };
+
+    while let Some(flag) = args.next() {
+        let mut value = || {
+            args.next()
+                .and_then(|value| value.parse().ok())
+                .ok_or(format!("{flag} expects an unsigned integer"))
+        };
+        match flag.as_str() {
+            "--seconds" => parsed.seconds = value()?,
+            "--dot" => parsed.dot = value()? as usize,
+            "--seed" => parsed.seed = value()?,
+            "--wiring" => parsed.layout = Layout::Wiring,
+            _ => return Err(format!("Unknown option {flag}")),
+        }
+    }
+    Ok(parsed)
+}
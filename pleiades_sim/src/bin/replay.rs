@@ -0,0 +1,71 @@
+//! Replays proximity traces through the gesture recognizer and prints
+//! the commands it emits. Options override the default `GestureConfig`:
+//!
+//! `cargo run --bin replay -- [--floor N] [--entry N] [--window N] [--near N] [--power N] [--calibrate N] [--level N] [--threshold N] <trace>...`
+
+use pleiades_core::gesture::GestureConfig;
+use pleiades_sim::trace;
+use std::fs::File;
+use std::io::BufReader;
+use std::process::exit;
+
+const USAGE: &str = "usage: replay [--floor N] [--entry N] [--window N] [--near N] [--power N] [--calibrate N] [--level N] [--threshold N] <trace>...";
+
+fn main() {
+    let (config, paths) = parse().unwrap_or_else(|message| {
+        eprintln!("{message}\n{USAGE}");
+        exit(2);
+    });
+
+    for path in paths {
+        let file = File::open(&path).unwrap_or_else(|err| {
+            eprintln!("{path}: {err}");
+            exit(1);
+        });
+        let samples = trace::read(BufReader::new(file)).unwrap_or_else(|err| {
+            eprintln!("{path}: {err}");
+            exit(1);
+        });
+
+        let duration = match (samples.first(), samples.last()) {
+            (Some(first), Some(last)) => last.millis.saturating_sub(first.millis),
+            _ => 0,
+        };
+        println!("{path}: {} samples over {duration} ms", samples.len());
+        for (millis, command) in trace::replay(&samples, config) {
+            println!("{millis:>8} ms  {command:?}");
+        }
+    }
+}
+
+fn parse() -> Result<(GestureConfig, Vec<String>), String> {
+    let mut config = GestureConfig::default();
+    let mut paths = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            paths.push(arg);
+            continue;
+        }
+
+        let value = args.next().ok_or(format!("{arg} expects a number"))?;
+        let invalid = |_| format!("{arg} does not take {value}");
+        match arg.as_str() {
+            "--floor" => config.floor = value.parse().map_err(invalid)?,
+            "--entry" => config.entry_checks = value.parse().map_err(invalid)?,
+            "--window" => config.swing_window = value.parse().map_err(invalid)?,
+            "--near" => config.near = value.parse().map_err(invalid)?,
+            "--power" => config.power_checks = value.parse().map_err(invalid)?,
+            "--calibrate" => config.calibrate_checks = value.parse().map_err(invalid)?,
+            "--level" => config.level_checks = value.parse().map_err(invalid)?,
+            "--threshold" => config.level_threshold = value.parse().map_err(invalid)?,
+            _ => return Err(format!("Unknown option {arg}")),
+        }
+    }
+
+    match paths.is_empty() {
+        true => Err("Missing trace".into()),
+        false => Ok((config, paths)),
+    }
+}
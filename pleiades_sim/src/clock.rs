@@ -0,0 +1,26 @@
+use embassy_time::{Duration, Instant, MockDriver};
+use futures::task::noop_waker;
+use std::future::Future;
+use std::pin::pin;
+use std::task::Context;
+
+/// Resolution of the virtual clock
+const STEP: Duration = Duration::from_millis(1);
+
+/// Poll `future` to completion, advancing the virtual clock while it
+/// waits on a timer, so a world tick takes no wall time at all.
+/// Returns the virtual time the future waited, i.e. the world's frame period.
+///
+/// The future must only ever wait on `embassy_time` timers.
+pub fn run(future: impl Future<Output = ()>) -> Duration {
+    let driver = MockDriver::get();
+    let waker = noop_waker();
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+
+    let start = Instant::now();
+    while future.as_mut().poll(&mut cx).is_pending() {
+        driver.advance(STEP);
+    }
+    Instant::now() - start
+}
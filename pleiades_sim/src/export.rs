@@ -0,0 +1,113 @@
+//! Animated GIF and APNG exports of recordings, every LED drawn as a
+//! square dot so designers can review worlds without a lantern.
+
+use crate::frame::Frame;
+use embassy_time::Duration;
+use pleiades_core::led_matrix::strip_index;
+use smart_leds::RGB8;
+use std::io::{self, Write};
+
+/// Where each LED ends up in the image
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum Layout {
+    /// As the world draws it, `x` to the right and `y` down
+    #[default]
+    Logical,
+    /// In the order the strip is wired: every column is `L` consecutive
+    /// LEDs, so odd columns show up flipped
+    Wiring,
+}
+
+pub struct Export {
+    /// Edge of the square drawn for one LED, in pixels
+    pub dot: usize,
+    /// Time every frame stays on screen, the world's ticker period
+    pub delay: Duration,
+    pub layout: Layout,
+}
+
+impl Export {
+    pub fn gif<const C: usize, const L: usize>(
+        &self,
+        out: &mut impl Write,
+        frames: &[Frame<C, L>],
+    ) -> io::Result<()> {
+        let (width, height) = self.size::<C, L>()?;
+        let mut encoder = gif::Encoder::new(out, width, height, &[]).map_err(io::Error::other)?;
+        encoder
+            .set_repeat(gif::Repeat::Infinite)
+            .map_err(io::Error::other)?;
+
+        // GIF counts in hundredths of a second
+        let delay = (self.delay.as_millis() / 10).clamp(1, u16::MAX as u64) as u16;
+        for frame in frames {
+            let mut frame = gif::Frame::from_rgb_speed(width, height, &self.raster(frame), 10);
+            frame.delay = delay;
+            encoder.write_frame(&frame).map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+
+    pub fn apng<const C: usize, const L: usize>(
+        &self,
+        out: &mut impl Write,
+        frames: &[Frame<C, L>],
+    ) -> io::Result<()> {
+        let (width, height) = self.size::<C, L>()?;
+        let mut encoder = png::Encoder::new(out, width.into(), height.into());
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .set_animated(frames.len() as u32, 0)
+            .map_err(io::Error::other)?;
+
+        let delay = self.delay.as_millis().min(u16::MAX as u64) as u16;
+        encoder
+            .set_frame_delay(delay, 1000)
+            .map_err(io::Error::other)?;
+
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        for frame in frames {
+            writer
+                .write_image_data(&self.raster(frame))
+                .map_err(io::Error::other)?;
+        }
+        writer.finish().map_err(io::Error::other)
+    }
+
+    fn size<const C: usize, const L: usize>(&self) -> io::Result<(u16, u16)> {
+        let side = |leds: usize| {
+            u16::try_from(leds * self.dot)
+                .ok()
+                .filter(|&side| side > 0)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Bad dot size"))
+        };
+        Ok((side(C)?, side(L)?))
+    }
+
+    /// RGB pixels of one frame, row by row
+    fn raster<const C: usize, const L: usize>(&self, frame: &Frame<C, L>) -> Vec<u8> {
+        let width = C * self.dot;
+        let mut pixels = vec![0; width * L * self.dot * 3];
+
+        for (x, column) in frame.iter().enumerate() {
+            for (y, &RGB8 { r, g, b }) in column.iter().enumerate() {
+                let (x, y) = match self.layout {
+                    Layout::Logical => (x, y),
+                    Layout::Wiring => {
+                        let index = strip_index::<L>(x, y);
+                        (index / L, index % L)
+                    }
+                };
+
+                for row in y * self.dot..(y + 1) * self.dot {
+                    let start = (row * width + x * self.dot) * 3;
+                    for pixel in pixels[start..start + self.dot * 3].chunks_exact_mut(3) {
+                        pixel.copy_from_slice(&[r, g, b]);
+                    }
+                }
+            }
+        }
+        pixels
+    }
+}
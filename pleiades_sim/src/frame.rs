@@ -0,0 +1,15 @@
+use smart_leds::RGB8;
+
+/// Frame in logical order, indexed as `frame[x][y]`
+pub type Frame<const C: usize, const L: usize> = [[RGB8; L]; C];
+
+/// Logical position of the LED at `index` along the serpentine
+/// wiring of the physical matrix. Inverse of `strip_index`.
+pub fn position<const L: usize>(index: usize) -> (usize, usize) {
+    let x = index / L;
+    let y = match x % 2 {
+        0 => index % L,
+        _ => L - index % L - 1,
+    };
+    (x, y)
+}
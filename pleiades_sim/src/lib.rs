@@ -0,0 +1,10 @@
+//! Host-side tooling to run Pleiades worlds on a workstation
+//! without flashing the lantern.
+
+pub mod clock;
+pub mod export;
+pub mod frame;
+pub mod ppm;
+pub mod record;
+pub mod terminal;
+pub mod trace;
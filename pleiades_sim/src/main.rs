@@ -0,0 +1,122 @@
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use pleiades_core::gesture::{Command, Direction};
+use pleiades_core::random::SeededRandom;
+use pleiades_core::world::{OnDirection, Switch, World};
+use pleiades_sim::clock;
+use pleiades_sim::terminal::{self, TerminalMatrix};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const NUM_LEDS_LINE: usize = 16;
+const NUM_LEDS_COLUMN: usize = 16;
+const NUM_LEDS: usize = NUM_LEDS_LINE * NUM_LEDS_COLUMN;
+
+const HELP: &str =
+    "space: swing  p: power  up/down: level  left/right: swipe  n/f: near/far  q: quit";
+
+fn main() {
+    // Pass the seed logged by the lantern to reproduce its animation
+    let seed = match std::env::args().nth(1) {
+        Some(seed) => seed.parse().expect("Seed must be an unsigned integer"),
+        None => SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_nanos() as u64,
+    };
+    let mut rng = SeededRandom::new(seed);
+
+    terminal::enter().expect("Failed to set up the terminal");
+
+    // Init 16x16 terminal matrix
+    let mut led_matrix: TerminalMatrix<NUM_LEDS_COLUMN, NUM_LEDS_LINE> =
+        TerminalMatrix::new(format!("seed: {seed}  {HELP}"));
+
+    // Create a new world
+    let mut world: World<'_, _, _, NUM_LEDS_COLUMN, NUM_LEDS_LINE, NUM_LEDS, { 2 * NUM_LEDS }> =
+        World::fire_new(&mut led_matrix, &mut rng);
+
+    let mut switch = Switch::new();
+
+    loop {
+        // Handle the commands from the keyboard
+        while let Some(command) = next_command() {
+            match command {
+                Command::Swipe(Direction::Right) => {
+                    world = switch.switch_world(&mut led_matrix, &mut rng)
+                }
+                Command::Swipe(Direction::Left) => {
+                    world = switch.previous_world(&mut led_matrix, &mut rng)
+                }
+                // A hand held close over the gesture engine
+                Command::Swipe(Direction::Near) => {
+                    world = switch.switch_power(&mut led_matrix, &mut rng)
+                }
+                Command::Swipe(Direction::Far) => {}
+                Command::Level(direction) | Command::Swipe(direction) => {
+                    world.on_direction(direction)
+                }
+                Command::Swing => world = switch.switch_world(&mut led_matrix, &mut rng),
+                Command::SwitchPower => world = switch.switch_power(&mut led_matrix, &mut rng),
+                Command::Power(on) => {
+                    if switch.set_power(on) {
+                        world = switch.current_world(&mut led_matrix, &mut rng)
+                    }
+                }
+                Command::World(index) => {
+                    world = switch.select_world(index, &mut led_matrix, &mut rng)
+                }
+                // No sensor to calibrate, no dimming of the terminal colors
+                Command::Calibrate | Command::Brightness(_) => {}
+            }
+        }
+
+        // World::tick is generated by macros. It runs on the virtual clock,
+        // so wait out the frame period for real.
+        let period = clock::run(World::tick(&mut world));
+        std::thread::sleep(period.into());
+    }
+}
+
+/// Keyboard stands in for the gesture sensor
+fn next_command() -> Option<Command> {
+    while event::poll(Duration::ZERO).unwrap_or(false) {
+        let Ok(Event::Key(key)) = event::read() else {
+            continue;
+        };
+
+        if is_quit(key) {
+            terminal::leave().expect("Failed to restore the terminal");
+            std::process::exit(0);
+        }
+
+        if let Some(command) = command(key) {
+            return Some(command);
+        }
+    }
+    None
+}
+
+fn command(key: KeyEvent) -> Option<Command> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+
+    match key.code {
+        KeyCode::Char(' ') | KeyCode::Char('s') => Some(Command::Swing),
+        KeyCode::Char('p') | KeyCode::Enter => Some(Command::SwitchPower),
+        KeyCode::Up | KeyCode::Char('k') => Some(Command::Level(Direction::Up)),
+        KeyCode::Down | KeyCode::Char('j') => Some(Command::Level(Direction::Down)),
+        KeyCode::Left | KeyCode::Char('h') => Some(Command::Swipe(Direction::Left)),
+        KeyCode::Right | KeyCode::Char('l') => Some(Command::Swipe(Direction::Right)),
+        KeyCode::Char('n') => Some(Command::Swipe(Direction::Near)),
+        KeyCode::Char('f') => Some(Command::Swipe(Direction::Far)),
+        _ => None,
+    }
+}
+
+fn is_quit(key: KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => true,
+        KeyCode::Char('c') => key.modifiers.contains(KeyModifiers::CONTROL),
+        _ => false,
+    }
+}
@@ -0,0 +1,65 @@
+//! Binary PPM (P6) filmstrips: frames are stacked top to bottom,
+//! so any image viewer can show a recording.
+
+use crate::frame::Frame;
+use smart_leds::RGB8;
+use std::io::{self, Read, Write};
+
+pub fn write<const C: usize, const L: usize>(
+    out: &mut impl Write,
+    frames: &[Frame<C, L>],
+) -> io::Result<()> {
+    write!(out, "P6\n{} {}\n255\n", C, L * frames.len())?;
+
+    for frame in frames {
+        for y in 0..L {
+            for column in frame {
+                let RGB8 { r, g, b } = column[y];
+                out.write_all(&[r, g, b])?;
+            }
+        }
+    }
+    out.flush()
+}
+
+pub fn read<const C: usize, const L: usize>(input: &mut impl Read) -> io::Result<Vec<Frame<C, L>>> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+
+    // Magic, width, height and max value, each followed by one whitespace
+    let mut header = data.splitn(5, |byte| byte.is_ascii_whitespace());
+    let mut field = || {
+        header
+            .next()
+            .and_then(|field| std::str::from_utf8(field).ok())
+            .ok_or_else(|| invalid("Truncated header"))
+    };
+    if field()? != "P6" {
+        return Err(invalid("Not a binary PPM"));
+    }
+    let width: usize = field()?.parse().map_err(|_| invalid("Bad width"))?;
+    let height: usize = field()?.parse().map_err(|_| invalid("Bad height"))?;
+    if field()? != "255" {
+        return Err(invalid("Only 8 bit colors are supported"));
+    }
+    let count = height / L;
+    if width != C || count * L != height {
+        return Err(invalid("Image does not match the matrix size"));
+    }
+
+    let pixels = header.next().unwrap_or_default();
+    if pixels.len() != width * height * 3 {
+        return Err(invalid("Pixel data does not match the header"));
+    }
+
+    let mut frames = vec![[[RGB8::default(); L]; C]; count];
+    for (i, rgb) in pixels.chunks_exact(3).enumerate() {
+        let (row, x) = (i / C, i % C);
+        frames[row / L][x][row % L] = RGB8::new(rgb[0], rgb[1], rgb[2]);
+    }
+    Ok(frames)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,55 @@
+use crate::frame::{self, Frame};
+use pleiades_core::led_matrix::WritableMatrix;
+use smart_leds::RGB8;
+
+/// Keeps every flushed frame in memory
+pub struct RecordingMatrix<const C: usize, const L: usize> {
+    data: Frame<C, L>,
+    frames: Vec<Frame<C, L>>,
+}
+
+impl<const C: usize, const L: usize> Default for RecordingMatrix<C, L> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const C: usize, const L: usize> RecordingMatrix<C, L> {
+    pub fn new() -> Self {
+        Self {
+            data: [[RGB8::default(); L]; C],
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn frames(&self) -> &[Frame<C, L>] {
+        &self.frames
+    }
+}
+
+impl<const C: usize, const L: usize> WritableMatrix for RecordingMatrix<C, L> {
+    fn write(&mut self, x: usize, y: usize, color: RGB8) {
+        self.data[x][y] = color;
+    }
+
+    fn write_straight(&mut self, index: usize, color: RGB8) {
+        let (x, y) = frame::position::<L>(index);
+        self.data[x][y] = color;
+    }
+
+    fn clear(&mut self) {
+        self.data = [[RGB8::default(); L]; C];
+    }
+
+    fn bg(&mut self, bg: RGB8) {
+        self.data = [[bg; L]; C];
+    }
+
+    fn read(&self, x: usize, y: usize) -> RGB8 {
+        self.data[x][y]
+    }
+
+    async fn flush(&mut self) {
+        self.frames.push(self.data);
+    }
+}
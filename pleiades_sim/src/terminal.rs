@@ -0,0 +1,94 @@
+use crate::frame::{self, Frame};
+use crossterm::style::{Color, Print, ResetColor, SetBackgroundColor};
+use crossterm::{cursor, execute, queue, terminal};
+use pleiades_core::led_matrix::WritableMatrix;
+use smart_leds::RGB8;
+use std::io::{self, Write};
+
+/// Switch the terminal to raw mode on the alternate screen.
+/// The terminal is restored on panic as well.
+pub fn enter() -> io::Result<()> {
+    let hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = leave();
+        hook(info);
+    }));
+
+    terminal::enable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        terminal::EnterAlternateScreen,
+        terminal::Clear(terminal::ClearType::All),
+        cursor::Hide
+    )
+}
+
+pub fn leave() -> io::Result<()> {
+    execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()
+}
+
+/// Renders the frame as truecolor blocks, two characters per LED
+/// to keep the pixels square.
+pub struct TerminalMatrix<const C: usize, const L: usize> {
+    data: Frame<C, L>,
+    footer: String,
+}
+
+impl<const C: usize, const L: usize> Default for TerminalMatrix<C, L> {
+    fn default() -> Self {
+        Self::new(String::new())
+    }
+}
+
+impl<const C: usize, const L: usize> TerminalMatrix<C, L> {
+    pub fn new(footer: String) -> Self {
+        Self {
+            data: [[RGB8::default(); L]; C],
+            footer,
+        }
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        queue!(out, cursor::MoveTo(0, 0))?;
+
+        for y in 0..L {
+            for x in 0..C {
+                let RGB8 { r, g, b } = self.data[x][y];
+                queue!(out, SetBackgroundColor(Color::Rgb { r, g, b }), Print("  "))?;
+            }
+            queue!(out, ResetColor, Print("\r\n"))?;
+        }
+        queue!(out, Print("\r\n"), Print(&self.footer))?;
+
+        out.flush()
+    }
+}
+
+impl<const C: usize, const L: usize> WritableMatrix for TerminalMatrix<C, L> {
+    fn write(&mut self, x: usize, y: usize, color: RGB8) {
+        self.data[x][y] = color;
+    }
+
+    fn write_straight(&mut self, index: usize, color: RGB8) {
+        let (x, y) = frame::position::<L>(index);
+        self.data[x][y] = color;
+    }
+
+    fn clear(&mut self) {
+        self.data = [[RGB8::default(); L]; C];
+    }
+
+    fn bg(&mut self, bg: RGB8) {
+        self.data = [[bg; L]; C];
+    }
+
+    fn read(&self, x: usize, y: usize) -> RGB8 {
+        self.data[x][y]
+    }
+
+    async fn flush(&mut self) {
+        self.draw(&mut io::stdout().lock())
+            .expect("Failed to draw the frame");
+    }
+}
@@ -0,0 +1,58 @@
+//! Proximity traces: the `PDATA` samples of `Apds9960::read`, captured
+//! from a lantern built with the `trace` feature.
+//!
+//! Every sample is a `pdata <millis> <value>` line. Anything before the
+//! `pdata` marker (defmt timestamps, log levels) and every other line is
+//! ignored, so a probe log can be replayed as is. `#` starts a comment.
+
+use pleiades_core::gesture::{Command, GestureConfig, StateMashine};
+use std::io::{self, BufRead};
+
+const MARKER: &str = "pdata";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sample {
+    /// Uptime of the lantern
+    pub millis: u64,
+    pub pdata: u8,
+}
+
+pub fn read(input: impl BufRead) -> io::Result<Vec<Sample>> {
+    let mut samples = Vec::new();
+
+    for (number, line) in input.lines().enumerate() {
+        let line = line?;
+        let line = line.split('#').next().unwrap_or_default();
+        let mut fields = line.split_whitespace().skip_while(|&field| field != MARKER);
+        if fields.next().is_none() {
+            continue;
+        }
+
+        let millis = fields.next().and_then(|millis| millis.parse().ok());
+        let pdata = fields.next().and_then(|pdata| pdata.parse().ok());
+        match (millis, pdata) {
+            (Some(millis), Some(pdata)) => samples.push(Sample { millis, pdata }),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Bad sample on line {}", number + 1),
+                ))
+            }
+        }
+    }
+    Ok(samples)
+}
+
+/// Feed `samples` to a fresh gesture recognizer, the way the sensor task
+/// does, and collect the commands along with the time they were emitted
+pub fn replay(samples: &[Sample], config: GestureConfig) -> Vec<(u64, Command)> {
+    let mut sm = StateMashine::new(config);
+
+    samples
+        .iter()
+        .filter_map(|sample| {
+            sm.next(sample.pdata);
+            sm.command().map(|command| (sample.millis, command))
+        })
+        .collect()
+}
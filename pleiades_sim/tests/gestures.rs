@@ -0,0 +1,123 @@
+//! Every proximity trace in `tests/traces/` is replayed through the
+//! gesture recognizer and must emit exactly the listed commands.
+//!
+//! Inspect a trace with `cargo run --bin replay -- tests/traces/<name>.trace`.
+
+use pleiades_core::gesture::Command::{self, Calibrate, Level, Swing, SwitchPower};
+use pleiades_core::gesture::Direction::{Down, Up};
+use pleiades_core::gesture::{GestureConfig, StateMashine};
+use pleiades_sim::trace;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+macro_rules! corpus {
+    ($name:ident, [$($command:expr),* $(,)?]) => {
+        #[test]
+        fn $name() {
+            check(stringify!($name), &[$($command),*]);
+        }
+    };
+}
+
+corpus!(swing, [Swing, Swing, Swing]);
+corpus!(power, [SwitchPower]);
+corpus!(noise, []);
+#[rustfmt::skip]
+corpus!(level, [
+    Level(Down), Level(Down), Level(Down), Level(Down), Level(Down),
+    Level(Up), Level(Up), Level(Up), Level(Up), Level(Up), Level(Up), Level(Up), Level(Up),
+]);
+
+fn check(name: &str, expected: &[Command]) {
+    check_with(name, GestureConfig::default(), expected);
+}
+
+#[test]
+fn tuned() {
+    let config = GestureConfig {
+        power_checks: 100,
+        ..Default::default()
+    };
+    check_with("power", config, &[]);
+
+    let config = GestureConfig {
+        calibrate_checks: 30,
+        ..Default::default()
+    };
+    // Held long enough to calibrate instead
+    check_with("power", config, &[Calibrate]);
+
+    let config = GestureConfig {
+        entry_checks: 15,
+        ..Default::default()
+    };
+    // The first swing is too short to register
+    check_with("swing", config, &[Swing, Swing]);
+}
+
+#[test]
+fn hold() {
+    let config = GestureConfig::default();
+    let hold = |checks: u32| {
+        let mut sm = StateMashine::new(config);
+        let mut commands = Vec::new();
+        // In, held close, then gone
+        let samples = [config.floor + 1; 40]
+            .into_iter()
+            .chain((0..checks).map(|_| config.near))
+            .chain([0; 5]);
+        for (i, dist) in samples.enumerate() {
+            sm.next(dist);
+            commands.extend(sm.command().map(|command| (i, command)));
+        }
+        commands
+    };
+    let release = |checks| 40 + checks as usize;
+
+    // Nothing until the hand is gone
+    assert_eq!(hold(100), [(release(100), SwitchPower)]);
+    assert_eq!(hold(config.power_checks), []);
+    // The calibration hold does not switch the power on the way
+    assert_eq!(hold(400), [(release(400), Calibrate)]);
+}
+
+fn check_with(name: &str, config: GestureConfig, expected: &[Command]) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "traces", name]
+        .iter()
+        .collect::<PathBuf>()
+        .with_extension("trace");
+    let file = File::open(&path).expect("Missing trace");
+    let samples = trace::read(BufReader::new(file)).expect("Failed to parse the trace");
+
+    let commands: Vec<Command> = trace::replay(&samples, config)
+        .into_iter()
+        .map(|(_, command)| command)
+        .collect();
+    assert_eq!(commands, expected, "Commands replayed from {name}");
+}
+
+#[test]
+fn probe_log() {
+    let log = "\
+0.000000 INFO  Seed: 42
+12.840000 pdata 12840 0
+12.850000 pdata 12850 140 # hand
+└─ pleiades::apds9960::{impl#0}::read::{async_fn#0} @ src/apds9960.rs:112
+";
+    let samples = trace::read(log.as_bytes()).expect("Failed to parse the log");
+    assert_eq!(
+        samples,
+        [
+            trace::Sample {
+                millis: 12840,
+                pdata: 0
+            },
+            trace::Sample {
+                millis: 12850,
+                pdata: 140
+            },
+        ]
+    );
+    assert!(trace::read("pdata 12840 256".as_bytes()).is_err());
+}
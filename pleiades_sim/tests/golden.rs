@@ -0,0 +1,98 @@
+//! Every world runs for a fixed number of ticks from a fixed seed and
+//! its frames are compared against the filmstrips in `tests/golden/`.
+//!
+//! After an intended visual change regenerate them with
+//! `UPDATE_GOLDEN=1 cargo test --test golden` and review the images.
+
+use pleiades_core::random::SeededRandom;
+use pleiades_core::world::World;
+use pleiades_sim::clock;
+use pleiades_sim::frame::Frame;
+use pleiades_sim::ppm;
+use pleiades_sim::record::RecordingMatrix;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+
+const NUM_LEDS_LINE: usize = 16;
+const NUM_LEDS_COLUMN: usize = 16;
+const NUM_LEDS: usize = NUM_LEDS_LINE * NUM_LEDS_COLUMN;
+
+const SEED: u64 = 0x5EED;
+const TICKS: usize = 64;
+
+type Recording = RecordingMatrix<NUM_LEDS_COLUMN, NUM_LEDS_LINE>;
+type Filmstrip = [Frame<NUM_LEDS_COLUMN, NUM_LEDS_LINE>];
+
+macro_rules! golden {
+    ($name:ident, $new:ident) => {
+        #[test]
+        fn $name() {
+            let mut led_matrix = Recording::new();
+            let mut rng = SeededRandom::new(SEED);
+
+            let mut world: World<
+                '_,
+                _,
+                _,
+                NUM_LEDS_COLUMN,
+                NUM_LEDS_LINE,
+                NUM_LEDS,
+                { 2 * NUM_LEDS },
+            > = World::$new(&mut led_matrix, &mut rng);
+
+            for _ in 0..TICKS {
+                clock::run(World::tick(&mut world));
+            }
+
+            check(stringify!($name), led_matrix.frames());
+        }
+    };
+}
+
+golden!(fire, fire_new);
+golden!(northen_light, northen_light_new);
+golden!(matrix, matrix_new);
+golden!(voronoi, voronoi_new);
+golden!(starry_night, starry_night_new);
+golden!(solid, solid_new);
+golden!(diagnostic, diagnostic_new);
+
+fn check(name: &str, actual: &Filmstrip) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{name}.ppm"));
+
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        let mut file = BufWriter::new(File::create(&path).unwrap());
+        ppm::write(&mut file, actual).unwrap();
+        return;
+    }
+
+    let expected = match File::open(&path) {
+        Ok(file) => ppm::read(&mut BufReader::new(file)).unwrap(),
+        Err(err) => panic!("Missing {path:?} ({err}), run with UPDATE_GOLDEN=1"),
+    };
+
+    if expected != actual {
+        // Keep the new frames around to compare side by side
+        let actual_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{name}.ppm"));
+        ppm::write(
+            &mut BufWriter::new(File::create(&actual_path).unwrap()),
+            actual,
+        )
+        .unwrap();
+
+        let frame = expected
+            .iter()
+            .zip(actual)
+            .position(|(expected, actual)| expected != actual)
+            .unwrap_or(expected.len().min(actual.len()));
+        panic!(
+            "{name}: frame {frame} differs from {path:?} ({} expected, {} actual frames). \
+             New frames are in {actual_path:?}",
+            expected.len(),
+            actual.len(),
+        );
+    }
+}
@@ -0,0 +1,93 @@
+//! The parameters the level gestures set come back with the worlds, after
+//! a switch or a power cycle, and the power follows the switch.
+
+use pleiades_core::gesture::Direction;
+use pleiades_core::random::SeededRandom;
+use pleiades_core::world::{OnDirection, Switch, World, NAMES};
+use pleiades_sim::record::RecordingMatrix;
+
+const NUM_LEDS_LINE: usize = 16;
+const NUM_LEDS_COLUMN: usize = 16;
+const NUM_LEDS: usize = NUM_LEDS_LINE * NUM_LEDS_COLUMN;
+
+type Recording = RecordingMatrix<NUM_LEDS_COLUMN, NUM_LEDS_LINE>;
+type Lantern<'led> = World<
+    'led,
+    Recording,
+    SeededRandom,
+    NUM_LEDS_COLUMN,
+    NUM_LEDS_LINE,
+    NUM_LEDS,
+    { 2 * NUM_LEDS },
+>;
+
+#[test]
+fn parameters() {
+    let mut led_matrix = Recording::new();
+    let mut rng = SeededRandom::new(0);
+
+    for (index, name) in NAMES.iter().enumerate() {
+        let mut switch = Switch::new();
+        let mut world: Lantern = switch.select_world(index, &mut led_matrix, &mut rng);
+        let initial = world.parameter();
+        assert!(initial.is_some(), "{name}");
+
+        // Past the cooldowns, up when already at the bottom
+        for direction in [Direction::Down, Direction::Up] {
+            for _ in 0..8 {
+                world.on_direction(direction);
+            }
+            if world.parameter() != initial {
+                break;
+            }
+        }
+        switch.remember(&world);
+        let changed = world.parameter();
+        assert_ne!(changed, initial, "{name}");
+
+        // Away and back
+        drop(world);
+        let _: Lantern = switch.switch_world(&mut led_matrix, &mut rng);
+        let world: Lantern = switch.select_world(index, &mut led_matrix, &mut rng);
+        assert_eq!(world.parameter(), changed, "{name}");
+        drop(world);
+
+        // After a power cycle
+        let mut switch = Switch::restore(index, true, switch.parameters());
+        let world: Lantern = switch.current_world(&mut led_matrix, &mut rng);
+        assert_eq!(world.parameter(), changed, "{name}");
+    }
+
+    // Nothing to keep while the power is off
+    let mut switch = Switch::new();
+    let world: Lantern = switch.switch_power(&mut led_matrix, &mut rng);
+    switch.remember(&world);
+    assert_eq!(switch.parameters(), [None; NAMES.len()]);
+}
+
+#[test]
+fn power() {
+    let mut led_matrix = Recording::new();
+    let mut rng = SeededRandom::new(0);
+    let mut switch = Switch::new();
+
+    // A swing while off brings a world back, a toggle or "off" still
+    // switches it off
+    let _: Lantern = switch.switch_power(&mut led_matrix, &mut rng);
+    let _: Lantern = switch.switch_world(&mut led_matrix, &mut rng);
+    assert!(switch.is_on());
+    let _: Lantern = switch.switch_power(&mut led_matrix, &mut rng);
+    assert!(!switch.is_on());
+    let _: Lantern = switch.switch_world(&mut led_matrix, &mut rng);
+    assert!(switch.set_power(false));
+    assert!(!switch.is_on());
+    assert!(!switch.set_power(false));
+
+    // And back on the same world
+    let world = switch.world();
+    assert!(switch.set_power(true));
+    assert!(!switch.set_power(true));
+    assert_eq!((switch.world(), switch.is_on()), (world, true));
+    let _: Lantern = switch.switch_power(&mut led_matrix, &mut rng);
+    assert!(!switch.is_on());
+}
@@ -0,0 +1,107 @@
+//! Pixels sent over UDP from the host, the way lighting software does,
+//! are parsed, mapped and shown by the stream world.
+
+use pleiades_core::random::SeededRandom;
+use pleiades_core::stream::{self, Frame, Layout, Watchdog};
+use pleiades_core::world::World;
+use pleiades_sim::clock;
+use pleiades_sim::record::RecordingMatrix;
+use smart_leds::RGB8;
+use std::net::UdpSocket;
+use std::time::Duration;
+
+const NUM_LEDS_LINE: usize = 16;
+const NUM_LEDS_COLUMN: usize = 16;
+const NUM_LEDS: usize = NUM_LEDS_LINE * NUM_LEDS_COLUMN;
+
+type Recording = RecordingMatrix<NUM_LEDS_COLUMN, NUM_LEDS_LINE>;
+
+fn e131_packet(universe: u16, data: &[u8]) -> Vec<u8> {
+    let mut packet = vec![0u8; 126];
+    packet[0..2].copy_from_slice(&[0x00, 0x10]);
+    packet[4..16].copy_from_slice(b"ASC-E1.17\0\0\0");
+    packet[18..22].copy_from_slice(&[0, 0, 0, 4]);
+    packet[40..44].copy_from_slice(&[0, 0, 0, 2]);
+    packet[108] = 100;
+    packet[113..115].copy_from_slice(&universe.to_be_bytes());
+    packet[117] = 0x02;
+    packet[118] = 0xA1;
+    packet[121..123].copy_from_slice(&1u16.to_be_bytes());
+    packet[123..125].copy_from_slice(&(data.len() as u16 + 1).to_be_bytes());
+    packet.extend_from_slice(data);
+    packet
+}
+
+fn artnet_packet(universe: u16, data: &[u8]) -> Vec<u8> {
+    let mut packet = stream::artnet::ID.to_vec();
+    packet.extend_from_slice(&0x5000u16.to_le_bytes());
+    packet.extend_from_slice(&[0, 14, 0, 0]);
+    packet.extend_from_slice(&universe.to_le_bytes());
+    packet.extend_from_slice(&(data.len() as u16).to_be_bytes());
+    packet.extend_from_slice(data);
+    packet
+}
+
+/// Receives until a packet completes `frame`, like `stream::receiver`
+fn receive(socket: &UdpSocket, layout: &Layout, frame: &mut Frame<NUM_LEDS>) {
+    let mut packet = [0u8; 640];
+    loop {
+        let len = socket.recv(&mut packet).unwrap();
+        if let Ok(dmx) = stream::parse(&packet[..len]) {
+            if frame.apply(layout, &dmx) {
+                return;
+            }
+        }
+    }
+}
+
+#[test]
+fn udp() {
+    // Ephemeral ports, the lantern listens on `e131::PORT` and `artnet::PORT`
+    let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+    receiver
+        .set_read_timeout(Some(Duration::from_secs(5)))
+        .unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+    sender.connect(receiver.local_addr().unwrap()).unwrap();
+
+    let layout = Layout::default();
+    let mut frame = Frame::new();
+    let mut led_matrix = Recording::new();
+    let mut rng = SeededRandom::new(0);
+    let mut watchdog = Watchdog::new();
+    {
+        let mut world: World<'_, _, _, NUM_LEDS_COLUMN, NUM_LEDS_LINE, NUM_LEDS, { 2 * NUM_LEDS }> =
+            World::stream_new(&mut led_matrix, &mut rng);
+
+        // Red over sACN on the first universe, blue over Art-Net on the second
+        let red: Vec<u8> = [255, 0, 0].repeat(170);
+        let blue: Vec<u8> = [0, 0, 255].repeat(170);
+        sender.send(&e131_packet(1, &red)).unwrap();
+        // Not ours
+        sender.send(&artnet_packet(9, &blue)).unwrap();
+        sender.send(&artnet_packet(2, &blue)).unwrap();
+
+        receive(&receiver, &layout, &mut frame);
+        receive(&receiver, &layout, &mut frame);
+        assert!(watchdog.feed(embassy_time::Instant::now()));
+        if let World::Stream(stream) = &mut world {
+            stream.show(&frame.pixels);
+        }
+        clock::run(World::tick(&mut world));
+    }
+
+    let frames = led_matrix.frames();
+    let last = frames.last().unwrap();
+    // Row after row
+    assert_eq!(last[0][0], RGB8::new(255, 0, 0));
+    assert_eq!(last[15][0], RGB8::new(255, 0, 0));
+    assert_eq!(last[9][10], RGB8::new(255, 0, 0));
+    assert_eq!(last[10][10], RGB8::new(0, 0, 255));
+    assert_eq!(last[15][15], RGB8::new(0, 0, 255));
+
+    // Falls back once the source goes quiet
+    assert!(!watchdog.expired(embassy_time::Instant::now()));
+    clock::run(embassy_time::Timer::after(stream::TIMEOUT));
+    assert!(watchdog.expired(embassy_time::Instant::now()));
+}